@@ -22,7 +22,16 @@ reset=Reset
 generate-icon=Generate Icon
 reset-icon=Reset icon
 
+invalid-records=Some web apps could not be loaded
+
 # header
+file=File
+import-webapps=Import web apps
+import-scripts=Web apps with custom code
+import-scripts-body=Some web apps of this bundle add their own styles or scripts to their pages. Scripts can read and change everything on these pages, only import them from people you trust.
+import-with-scripts=Import with custom code
+import-without-scripts=Import without custom code
+export-webapps=Export web apps
 main-window={ $app }
 view=View
 create=Done
@@ -62,22 +71,87 @@ browser=Browser
 new-webapp-title=New Quick Web App
 title=Title
 url=URL
+fetch-details=Fetch details
+fetching-details=Fetching...
+fetch-details-failed=Details could not be fetched
 download-favicon=Download favicon
 non-standard-arguments=Non-standard arguments
 # keep navbar, isolated profile nad private mode small count of characters
 navbar=Nav Bar
 persistent-profile=Persistent Profile
 isolated-profile=Isolated Profile
+shared-profile=Shared Profile
 private-mode=Private Mode
+allowed-origins=Other sites kept in the app
+download-dir=Download folder
+change=Change
+remember-window=Remember last window size and position
 window-size=Window Size
 decorations=Window Decorations
 simulate-mobile=Try to simulate mobile device
+device=Device
+notifications=Allow notifications
+header-bar=Show navigation buttons
+run-in-background=Keep running in the tray when closed
+default-zoom=Default zoom
+user-agent=User agent
+default-user-agent=Default user agent of the bundled browser
+desktop-entry=Launcher
+comment=Comment
+keywords=Keywords
+mime-types=Handled link types
+handle-links=Open matching links in this app
+url-patterns=Link patterns
+protocol-handlers=Pages opening other links
+startup-notify=Startup notification
+desktop-actions=Quick links
+action-name=Name
+add-action=Add quick link
+site-permissions=Site permissions
+no-site-permissions=No site asked for permissions yet
+permission-allowed=Allowed
+permission-denied=Denied
+revoke=Revoke
+user-content=Custom style and script
+user-css=CSS added to every page
+user-js=JavaScript run on every page
+content-blocking=Content blocking
+block-content=Block ads and trackers
+blocking-allowlist=Sites never blocked on
+filter-lists=Filter lists
+update-filter-lists=Update
+filter-lists-updating=Downloading filter lists...
+filter-lists-updated=Updated { $count ->
+        [1] 1 filter list
+        *[other] { $count } filter lists
+    }
+filter-lists-failed=Filter lists could not be downloaded
+permission-camera=Camera
+permission-microphone=Microphone
+permission-screen-capture=Screen sharing
+permission-geolocation=Location
+permission-clipboard=Clipboard
+
+# profiles.rs
+profiles=Profiles
+profile-name=Profile name
+profile-create=Create profile
+profile-rename=Rename
+profile-duplicate=Duplicate
+clear-cache=Clear cache
+clear-cookies=Clear cookies
+disk-usage=Disk usage
+used-by=Used by
+not-used=No web apps
+no-profiles=There are no profiles yet.
+isolated-profile-of={ $app } (isolated)
 
 # iconpicker.rs
 icon-name-to-find=Icon name to find
 my-icons=My icons
 download=Download
 search=Search
+no-site-icons=The site doesn't offer any icon
 
 # icons_installator.rs
 icons-installer-header=Please wait. Downloading icons...
@@ -93,3 +167,35 @@ warning=You don't meet requirements
     .app-url=  - You must provide valid URL starting with http:// or https://
     .app-icon=  - You must select an Icon for your launcher
     .app-browser=  - Please select a browser. Make sure at least one is installed system-wide or via Flatpak
+
+# webview
+save-download=Save Download
+save=Save
+downloading=Downloading
+download-finished=Download finished
+download-failed=Download failed
+permission-prompt={ $origin } wants to use: { $permissions }
+permission-allow=Allow
+permission-deny=Deny
+permission-always-allow=Always allow
+permission-always-deny=Always deny
+error-offline=You're offline
+error-offline-message=The page will reload as soon as you're connected again.
+error-unreachable={ $app } can't be reached
+error-unreachable-message=The site may be down or your connection may be interrupted. The page will reload once the site responds.
+error-details=Details
+retry=Try again
+find-in-page=Find in page
+find-previous=Previous match
+find-next=Next match
+find-no-matches=No matches
+back=Back
+forward=Forward
+reload=Reload
+tray-show=Show
+tray-quit=Quit
+tray-unavailable=No tray available, closing the window quits the app
+tray-unread={ $count ->
+    [one] 1 unread
+   *[other] { $count } unread
+}
@@ -0,0 +1,668 @@
+use anyhow::{Context as _, bail};
+use clap::{Args, Parser, Subcommand};
+use std::{
+    io::Read as _,
+    path::{Path, PathBuf},
+};
+use strum::IntoEnumIterator as _;
+use webapps::{
+    Category, WindowSize,
+    browser::{Browser, ProtocolHandler},
+    bundle::Bundle,
+    device::{self, Device},
+    launcher::{DesktopAction, WebAppLauncher, WebappIcon, installed_webapps},
+};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// List installed web apps
+    List,
+    /// Show details of a web app
+    Show { id: String },
+    /// Create a new web app
+    Create(CreateArgs),
+    /// Change an existing web app
+    Edit(EditArgs),
+    /// Delete a web app together with its launcher
+    Delete { id: String },
+    /// Launch a web app
+    Launch {
+        id: String,
+        /// Page of the web app to open instead of its start page
+        url: Option<String>,
+    },
+    /// Export web apps into a portable bundle
+    Export {
+        /// Ids of web apps to export, all web apps when omitted
+        ids: Vec<String>,
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Install every web app from a bundle
+    Import {
+        bundle: PathBuf,
+        /// Keep the styles and scripts the web apps add to their pages
+        #[arg(long)]
+        custom_code: bool,
+    },
+    /// Open a URL in the web app claiming it, or in the regular browser
+    OpenUrl { url: String },
+    /// Download the filter lists used for content blocking
+    UpdateFilters,
+}
+
+#[derive(Args, Debug)]
+pub struct CreateArgs {
+    #[arg(long)]
+    pub name: String,
+    #[arg(long)]
+    pub url: String,
+    /// Path to a PNG or SVG icon. A letter icon is generated when omitted.
+    #[arg(long)]
+    pub icon: Option<PathBuf>,
+    #[arg(long, value_parser = parse_category, default_value = "Utility")]
+    pub category: Category,
+    #[arg(long)]
+    pub width: Option<u32>,
+    #[arg(long)]
+    pub height: Option<u32>,
+    #[arg(long)]
+    pub mobile: bool,
+    /// Restore the last window size and position instead of a fixed size
+    #[arg(long)]
+    pub remember_window: bool,
+    /// Let the page show desktop notifications
+    #[arg(long)]
+    pub notifications: bool,
+    /// Show back, forward, reload and find buttons above the page
+    #[arg(long)]
+    pub header_bar: bool,
+    /// Keep running with a tray icon when the window is closed
+    #[arg(long)]
+    pub background: bool,
+    /// Page zoom in percent, the nearest of the editor's presets is used
+    #[arg(long)]
+    pub zoom: Option<u32>,
+    /// User agent replacing the default one, `{chrome}` stands for the bundled Chrome version
+    #[arg(long)]
+    pub user_agent: Option<String>,
+    /// Device emulated with `--mobile`, its size is the default window size
+    #[arg(long, value_parser = parse_device)]
+    pub device: Option<&'static Device>,
+    /// Folder the save dialog opens for downloads
+    #[arg(long)]
+    pub download_dir: Option<PathBuf>,
+    /// Origin kept in the app instead of opening in the default browser, can be repeated
+    #[arg(long = "allow-origin")]
+    pub allowed_origins: Vec<String>,
+    /// Share the named profile with other web apps instead of an isolated one
+    #[arg(long)]
+    pub shared_profile: Option<String>,
+    /// Open links matching the pattern in this web app, e.g. `https://mail.example.com/*`
+    #[arg(long = "handle-url", value_parser = parse_url_pattern)]
+    pub url_handlers: Vec<String>,
+    /// Open links of another scheme as `SCHEME=URL`, `%s` in the url stands for the link,
+    /// e.g. `mailto=https://mail.example.com/compose?to=%s`. Can be repeated
+    #[arg(long = "protocol-handler", value_parser = parse_protocol_handler)]
+    pub protocol_handlers: Vec<ProtocolHandler>,
+    /// Block ads and trackers with the filter lists
+    #[arg(long)]
+    pub block_content: bool,
+    /// Host whose pages are never filtered, can be repeated
+    #[arg(long = "unblock-host")]
+    pub blocking_allowlist: Vec<String>,
+    #[command(flatten)]
+    pub desktop_entry: DesktopEntryArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct EditArgs {
+    pub id: String,
+    #[arg(long)]
+    pub name: Option<String>,
+    #[arg(long)]
+    pub url: Option<String>,
+    #[arg(long)]
+    pub icon: Option<PathBuf>,
+    #[arg(long, value_parser = parse_category)]
+    pub category: Option<Category>,
+    #[arg(long)]
+    pub width: Option<u32>,
+    #[arg(long)]
+    pub height: Option<u32>,
+    #[arg(long)]
+    pub mobile: Option<bool>,
+    #[arg(long)]
+    pub remember_window: Option<bool>,
+    #[arg(long)]
+    pub notifications: Option<bool>,
+    #[arg(long)]
+    pub header_bar: Option<bool>,
+    #[arg(long)]
+    pub background: Option<bool>,
+    /// Page zoom in percent, the nearest of the editor's presets is used
+    #[arg(long)]
+    pub zoom: Option<u32>,
+    /// Replace the user agent, an empty one restores the default
+    #[arg(long)]
+    pub user_agent: Option<String>,
+    /// Emulate another device and resize the window to it
+    #[arg(long, value_parser = parse_device)]
+    pub device: Option<&'static Device>,
+    #[arg(long)]
+    pub download_dir: Option<PathBuf>,
+    /// Replace the origins kept in the app
+    #[arg(long = "allow-origin")]
+    pub allowed_origins: Vec<String>,
+    #[arg(long, conflicts_with = "isolated")]
+    pub shared_profile: Option<String>,
+    /// Switch back to an isolated profile
+    #[arg(long)]
+    pub isolated: bool,
+    /// Replace the URL patterns opened in this web app
+    #[arg(
+        long = "handle-url",
+        value_parser = parse_url_pattern,
+        conflicts_with = "no_url_handlers"
+    )]
+    pub url_handlers: Vec<String>,
+    /// Stop opening links in this web app
+    #[arg(long)]
+    pub no_url_handlers: bool,
+    /// Replace the pages opening links of other schemes
+    #[arg(
+        long = "protocol-handler",
+        value_parser = parse_protocol_handler,
+        conflicts_with = "no_protocol_handlers"
+    )]
+    pub protocol_handlers: Vec<ProtocolHandler>,
+    /// Stop opening links of other schemes in this web app
+    #[arg(long)]
+    pub no_protocol_handlers: bool,
+    #[arg(long)]
+    pub block_content: Option<bool>,
+    /// Replace the hosts whose pages are never filtered
+    #[arg(long = "unblock-host")]
+    pub blocking_allowlist: Vec<String>,
+    #[command(flatten)]
+    pub desktop_entry: DesktopEntryArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct DesktopEntryArgs {
+    /// Tooltip shown by the launcher
+    #[arg(long)]
+    pub comment: Option<String>,
+    /// Additional search keyword, can be repeated
+    #[arg(long = "keyword")]
+    pub keywords: Vec<String>,
+    /// `x-scheme-handler/SCHEME` type opened by a `--protocol-handler`, can be repeated
+    #[arg(long = "mime-type", value_parser = parse_mime_type)]
+    pub mime_types: Vec<String>,
+    #[arg(long)]
+    pub startup_notify: Option<bool>,
+    /// Quick link in the launcher context menu as `Name=URL`, can be repeated
+    #[arg(long = "action", value_parser = parse_action)]
+    pub actions: Vec<DesktopAction>,
+}
+
+impl DesktopEntryArgs {
+    fn apply(self, launcher: &mut WebAppLauncher) {
+        if let Some(comment) = self.comment {
+            launcher.comment = Some(comment).filter(|c| !c.is_empty());
+        }
+        if !self.keywords.is_empty() {
+            launcher.keywords = Some(self.keywords);
+        }
+        if !self.mime_types.is_empty() {
+            launcher.mime_types = Some(self.mime_types);
+        }
+        if let Some(startup_notify) = self.startup_notify {
+            launcher.startup_notify = Some(startup_notify);
+        }
+        if !self.actions.is_empty() {
+            launcher.actions = Some(self.actions);
+        }
+    }
+}
+
+fn parse_category(value: &str) -> Result<Category, String> {
+    Category::iter()
+        .find(|category| category.as_ref().eq_ignore_ascii_case(value))
+        .ok_or_else(|| {
+            let names = Category::iter()
+                .map(|category| category.as_ref().to_string())
+                .collect::<Vec<String>>();
+
+            format!("expected one of: {}", names.join(", "))
+        })
+}
+
+fn parse_device(value: &str) -> Result<&'static Device, String> {
+    device::PRESETS
+        .iter()
+        .find(|device| device.name.eq_ignore_ascii_case(value))
+        .ok_or_else(|| {
+            let names = device::PRESETS
+                .iter()
+                .map(|device| device.name)
+                .collect::<Vec<&str>>();
+
+            format!("expected one of: {}", names.join(", "))
+        })
+}
+
+fn parse_action(value: &str) -> Result<DesktopAction, String> {
+    let (name, url) = value
+        .split_once('=')
+        .ok_or_else(|| String::from("expected NAME=URL"))?;
+
+    if name.trim().is_empty() || !webapps::url_valid(url) {
+        return Err(String::from("expected a name and a valid url"));
+    }
+
+    Ok(DesktopAction {
+        name: name.trim().to_string(),
+        url: url.to_string(),
+    })
+}
+
+fn parse_mime_type(value: &str) -> Result<String, String> {
+    // the webview opens links, files have no page to go to
+    match value.strip_prefix("x-scheme-handler/") {
+        Some(scheme) if !scheme.is_empty() => Ok(value.to_string()),
+        _ => Err(String::from("expected x-scheme-handler/SCHEME")),
+    }
+}
+
+fn parse_url_pattern(value: &str) -> Result<String, String> {
+    match webapps::url_handler::pattern_scheme(value).as_deref() {
+        Some("http" | "https") if value.contains("://") => Ok(value.to_string()),
+        _ => Err(String::from(
+            "expected an http(s) pattern like https://mail.example.com/*, \
+            links of other schemes need --protocol-handler",
+        )),
+    }
+}
+
+fn parse_protocol_handler(value: &str) -> Result<ProtocolHandler, String> {
+    ProtocolHandler::parse(value).ok_or_else(|| {
+        String::from("expected SCHEME=URL, with a scheme other than http(s) and %s in the url")
+    })
+}
+
+pub fn run(command: Command) -> i32 {
+    // runs for every link clicked while the dispatcher is the default browser, so it stays
+    // clear of the runtime
+    if let Command::OpenUrl { url } = &command {
+        return exit_code(webapps::url_handler::dispatch(url));
+    }
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("failed to start runtime: {e}");
+            return 1;
+        }
+    };
+
+    let result = runtime.block_on(async {
+        match command {
+            Command::List => list(),
+            Command::Show { id } => show(&id),
+            Command::Create(args) => create(args).await,
+            Command::Edit(args) => edit(args).await,
+            Command::Delete { id } => delete(&id).await,
+            Command::Launch { id, url } => launch(&id, url.as_deref()),
+            Command::Export { ids, output } => export(&ids, &output),
+            Command::Import {
+                bundle,
+                custom_code,
+            } => import(&bundle, custom_code).await,
+            Command::OpenUrl { url } => webapps::url_handler::dispatch(&url),
+            Command::UpdateFilters => update_filters(),
+        }
+    });
+
+    exit_code(result)
+}
+
+fn exit_code(result: anyhow::Result<()>) -> i32 {
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("error: {e:#}");
+            1
+        }
+    }
+}
+
+fn find(id: &str) -> anyhow::Result<WebAppLauncher> {
+    WebAppLauncher::from_appid(id).with_context(|| format!("no web app with id {id}"))
+}
+
+fn load_icon(path: PathBuf) -> anyhow::Result<WebappIcon> {
+    let path = path
+        .canonicalize()
+        .with_context(|| format!("icon {} not found", path.display()))?;
+
+    let mut buffer = Vec::new();
+    std::fs::File::open(&path)?.read_to_end(&mut buffer)?;
+
+    Ok(WebappIcon { path, buffer })
+}
+
+async fn install(launcher: &WebAppLauncher) -> anyhow::Result<()> {
+    if !webapps::launcher::webapplauncher_is_valid(&launcher.name, &launcher.browser.url) {
+        bail!("name must not be empty and url must be valid");
+    }
+
+    if !launcher.create().await? {
+        bail!("failed to install launcher, make sure CEF is installed and the icon is valid");
+    }
+
+    launcher.save()
+}
+
+fn list() -> anyhow::Result<()> {
+    let (launchers, invalid) = webapps::launcher::load_database();
+
+    for record in invalid {
+        eprintln!("invalid record {}: {}", record.path.display(), record.error);
+    }
+
+    for launcher in launchers {
+        println!(
+            "{}\t{}\t{}",
+            launcher.browser.app_id.as_ref(),
+            launcher.name,
+            launcher.browser.url.unwrap_or_default()
+        );
+    }
+
+    Ok(())
+}
+
+fn show(id: &str) -> anyhow::Result<()> {
+    let launcher = find(id)?;
+    let browser = &launcher.browser;
+
+    println!("id:       {}", browser.app_id.as_ref());
+    println!("name:     {}", launcher.name);
+    println!("url:      {}", browser.url.clone().unwrap_or_default());
+    println!("category: {}", launcher.category.as_ref());
+    println!("icon:     {}", launcher.icon.path.display());
+    println!("profile:  {}", browser.profile.display());
+    println!(
+        "shared:   {}",
+        browser
+            .shared_profile()
+            .unwrap_or_else(|| String::from("no"))
+    );
+    println!(
+        "window:   {}",
+        browser.window_size.clone().unwrap_or_default()
+    );
+    println!(
+        "mobile:   {}",
+        browser.try_simulate_mobile.unwrap_or_default()
+    );
+    println!(
+        "remember: {}",
+        browser.remember_window_state.unwrap_or_default()
+    );
+    println!("notify:   {}", browser.notifications.unwrap_or_default());
+    println!("header:   {}", browser.header_bar.unwrap_or_default());
+    println!(
+        "tray:     {}",
+        browser.run_in_background.unwrap_or_default()
+    );
+    println!(
+        "zoom:     {}%",
+        browser.zoom.unwrap_or(webapps::DEFAULT_ZOOM)
+    );
+    if let Some(device) = browser.device() {
+        println!("device:   {}", device.name);
+    }
+    if let Some(user_agent) = &browser.user_agent {
+        println!("agent:    {}", user_agent);
+    }
+    println!("download: {}", browser.download_location().display());
+    println!("blocking: {}", browser.content_blocking.unwrap_or_default());
+    if let Some(comment) = &launcher.comment {
+        println!("comment:  {}", comment);
+    }
+    if let Some(keywords) = &launcher.keywords {
+        println!("keywords: {}", keywords.join(", "));
+    }
+    if let Some(mime_types) = &launcher.mime_types {
+        println!("mime:     {}", mime_types.join(", "));
+    }
+    for origin in browser.allowed_origins.iter().flatten() {
+        println!("allows:   {}", origin);
+    }
+    for pattern in browser.url_handlers.iter().flatten() {
+        println!("handles:  {}", pattern);
+    }
+    for handler in browser.protocol_handlers.iter().flatten() {
+        println!("handles:  {}: with {}", handler.protocol, handler.url);
+    }
+    for action in launcher.actions.iter().flatten() {
+        println!("action:   {}={}", action.name, action.url);
+    }
+    for host in browser.blocking_allowlist.iter().flatten() {
+        println!("unblocks: {}", host);
+    }
+
+    Ok(())
+}
+
+async fn create(args: CreateArgs) -> anyhow::Result<()> {
+    let icon = match args.icon {
+        Some(path) => load_icon(path)?,
+        None => {
+            let letter = args.name.chars().next().context("name must not be empty")?;
+            webapps::generate_icon(&letter.to_string()).context("failed to generate icon")?
+        }
+    };
+
+    let app_id = webapps::browser::app_id_from_name(&args.name);
+    let mut browser = Browser::new(&app_id);
+    browser.window_title = Some(args.name.clone());
+    browser.url = Some(args.url);
+    let default_size = match (args.mobile, args.device) {
+        (true, device) => device.unwrap_or(&device::PRESETS[0]).window_size(),
+        (false, _) => WindowSize::default(),
+    };
+    browser.window_size = Some(WindowSize(
+        args.width.unwrap_or(default_size.0),
+        args.height.unwrap_or(default_size.1),
+    ));
+    browser.try_simulate_mobile = Some(args.mobile);
+    browser.device = args.device.map(|device| device.name.to_string());
+    browser.remember_window_state = Some(args.remember_window);
+    browser.notifications = Some(args.notifications);
+    browser.header_bar = Some(args.header_bar);
+    browser.run_in_background = Some(args.background);
+    browser.zoom = args.zoom.map(webapps::nearest_zoom_preset);
+    browser.user_agent = args.user_agent.filter(|user_agent| !user_agent.is_empty());
+    browser.download_dir = args.download_dir;
+    browser.allowed_origins = Some(args.allowed_origins);
+    if let Some(profile) = args.shared_profile {
+        browser.set_profile(false, &profile);
+    }
+    if !args.url_handlers.is_empty() {
+        browser.url_handlers = Some(args.url_handlers);
+    }
+    if !args.protocol_handlers.is_empty() {
+        browser.protocol_handlers = Some(args.protocol_handlers);
+    }
+    browser.content_blocking = Some(args.block_content);
+    browser.blocking_allowlist = Some(args.blocking_allowlist);
+
+    let mut launcher = WebAppLauncher::new(browser, args.name, icon, args.category);
+    args.desktop_entry.apply(&mut launcher);
+
+    install(&launcher).await?;
+    println!("{}", app_id);
+
+    Ok(())
+}
+
+async fn edit(args: EditArgs) -> anyhow::Result<()> {
+    let mut launcher = find(&args.id)?;
+
+    if let Some(name) = args.name {
+        launcher.browser.window_title = Some(name.clone());
+        launcher.name = name;
+    }
+    if let Some(url) = args.url {
+        launcher.browser.url = Some(url);
+    }
+    if let Some(path) = args.icon {
+        launcher.icon = load_icon(path)?;
+    }
+    if let Some(category) = args.category {
+        launcher.category = category;
+    }
+    if args.width.is_some() || args.height.is_some() {
+        let current = launcher.browser.window_size.clone().unwrap_or_default();
+
+        launcher.browser.window_size = Some(WindowSize(
+            args.width.unwrap_or(current.0),
+            args.height.unwrap_or(current.1),
+        ));
+    }
+    if let Some(mobile) = args.mobile {
+        launcher.browser.try_simulate_mobile = Some(mobile);
+    }
+    if let Some(device) = args.device {
+        launcher.browser.device = Some(device.name.to_string());
+
+        let size = device.window_size();
+        launcher.browser.window_size = Some(WindowSize(
+            args.width.unwrap_or(size.0),
+            args.height.unwrap_or(size.1),
+        ));
+    }
+    if let Some(remember) = args.remember_window {
+        launcher.browser.remember_window_state = Some(remember);
+    }
+    if let Some(notifications) = args.notifications {
+        launcher.browser.notifications = Some(notifications);
+    }
+    if let Some(header_bar) = args.header_bar {
+        launcher.browser.header_bar = Some(header_bar);
+    }
+    if let Some(background) = args.background {
+        launcher.browser.run_in_background = Some(background);
+    }
+    if let Some(zoom) = args.zoom {
+        launcher.browser.zoom = Some(webapps::nearest_zoom_preset(zoom));
+    }
+    if let Some(user_agent) = args.user_agent {
+        launcher.browser.user_agent = Some(user_agent).filter(|user_agent| !user_agent.is_empty());
+    }
+    if let Some(download_dir) = args.download_dir {
+        launcher.browser.download_dir = Some(download_dir);
+    }
+    if !args.allowed_origins.is_empty() {
+        launcher.browser.allowed_origins = Some(args.allowed_origins);
+    }
+    if let Some(profile) = args.shared_profile {
+        launcher.browser.set_profile(false, &profile);
+    } else if args.isolated {
+        launcher.browser.set_profile(true, "");
+    }
+    if !args.url_handlers.is_empty() {
+        launcher.browser.url_handlers = Some(args.url_handlers);
+    } else if args.no_url_handlers {
+        launcher.browser.url_handlers = None;
+    }
+    if !args.protocol_handlers.is_empty() {
+        launcher.browser.protocol_handlers = Some(args.protocol_handlers);
+    } else if args.no_protocol_handlers {
+        launcher.browser.protocol_handlers = None;
+    }
+    if let Some(block_content) = args.block_content {
+        launcher.browser.content_blocking = Some(block_content);
+    }
+    if !args.blocking_allowlist.is_empty() {
+        launcher.browser.blocking_allowlist = Some(args.blocking_allowlist);
+    }
+    args.desktop_entry.apply(&mut launcher);
+
+    install(&launcher).await
+}
+
+fn update_filters() -> anyhow::Result<()> {
+    let count = webapps::content_filter::update_lists()?;
+    println!("updated {} filter lists", count);
+
+    Ok(())
+}
+
+async fn delete(id: &str) -> anyhow::Result<()> {
+    find(id)?.delete().await?;
+
+    Ok(())
+}
+
+fn launch(id: &str, url: Option<&str>) -> anyhow::Result<()> {
+    find(id)?.browser.launch(url)
+}
+
+fn export(ids: &[String], output: &Path) -> anyhow::Result<()> {
+    let launchers = if ids.is_empty() {
+        installed_webapps()
+    } else {
+        ids.iter()
+            .map(|id| find(id))
+            .collect::<anyhow::Result<_>>()?
+    };
+
+    Bundle::new(&launchers).export(output)?;
+    println!("exported {} web apps", launchers.len());
+
+    Ok(())
+}
+
+async fn import(path: &Path, custom_code: bool) -> anyhow::Result<()> {
+    let mut bundle = Bundle::load(path)?;
+
+    // custom code runs with the rights of the page, it's only imported when asked for
+    if !custom_code {
+        for app in bundle.apps_with_custom_code() {
+            eprintln!(
+                "warning: skipping the custom code of {}, pass --custom-code to keep it",
+                app.name
+            );
+        }
+        bundle.strip_custom_code();
+    }
+
+    let mut failed = 0;
+
+    for result in bundle.import().await {
+        match result {
+            Ok(launcher) => println!("{}\t{}", launcher.browser.app_id.as_ref(), launcher.name),
+            Err(e) => {
+                eprintln!("error: {e:#}");
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!("{failed} web apps could not be imported");
+    }
+
+    Ok(())
+}
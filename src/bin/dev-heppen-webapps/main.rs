@@ -1,5 +1,7 @@
+use clap::Parser as _;
 use i18n_embed::DesktopLanguageRequester;
 
+pub(crate) mod cli;
 pub(crate) mod config;
 pub(crate) mod pages;
 pub(crate) mod themes;
@@ -8,6 +10,10 @@ fn main() -> cosmic::iced::Result {
     init_logging();
     init_localizer();
 
+    if let Some(command) = cli::Cli::parse().command {
+        std::process::exit(cli::run(command));
+    }
+
     cosmic::app::run::<crate::pages::QuickWebApps>(
         cosmic::app::Settings::default()
             .antialiasing(true)
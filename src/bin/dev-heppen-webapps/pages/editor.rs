@@ -1,3 +1,4 @@
+use ashpd::desktop::file_chooser::SelectedFiles;
 use cosmic::{
     Element, Task,
     action::Action,
@@ -5,11 +6,15 @@ use cosmic::{
     style, task,
     widget::{self},
 };
-use rand::{RngExt as _, rng};
+use std::path::PathBuf;
 use strum::IntoEnumIterator as _;
 use webapps::{
-    Category, fl, generate_icon, handle_icon,
-    launcher::{WebappIcon, webapp_icon_valid},
+    Category,
+    browser::ProtocolHandler,
+    fl, generate_icon, handle_icon,
+    launcher::{DesktopAction, WebappIcon, webapp_icon_valid},
+    manifest::SiteDetails,
+    permissions::{Decision, Permission, SitePermissions},
 };
 
 use crate::pages;
@@ -24,12 +29,44 @@ pub struct AppEditor {
     pub app_window_width: String,
     pub app_window_height: String,
     pub app_window_size: webapps::WindowSize,
+    pub app_remember_window: bool,
+    pub app_download_dir: Option<PathBuf>,
+    pub app_allowed_origins: String,
     pub app_isolated: bool,
+    pub app_shared_profile: String,
+    pub profiles: Vec<String>,
     pub app_simulate_mobile: bool,
+    pub app_device: usize,
+    pub devices: Vec<String>,
+    pub app_notifications: bool,
+    pub app_header_bar: bool,
+    pub app_run_in_background: bool,
+    pub app_zoom: u32,
+    pub zoom_presets: Vec<String>,
+    pub app_user_agent: String,
+    pub user_agent_presets: Vec<String>,
     pub selected_icon: Option<webapps::Icon>,
     pub categories: Vec<String>,
     pub category_idx: Option<usize>,
     pub is_installed: bool,
+    pub app_comment: String,
+    pub app_keywords: String,
+    pub app_mime_types: String,
+    pub app_startup_notify: bool,
+    pub app_actions: Vec<DesktopAction>,
+    pub app_handle_links: bool,
+    pub app_url_handlers: String,
+    pub app_protocol_handlers: String,
+    pub app_permissions: SitePermissions,
+    pub app_user_css: SourceText,
+    pub app_user_js: SourceText,
+    pub app_content_blocking: bool,
+    pub app_blocking_allowlist: String,
+    pub filter_lists_status: Option<String>,
+    pub app_theme_color: Option<String>,
+    pub app_display: Option<String>,
+    pub fetching_details: bool,
+    pub fetch_details_error: Option<String>,
 }
 
 impl Default for AppEditor {
@@ -38,6 +75,15 @@ impl Default for AppEditor {
             .map(|c| c.name())
             .collect::<Vec<String>>();
 
+        let mut profiles = webapps::profiles::Profile::list()
+            .into_iter()
+            .map(|profile| profile.name)
+            .collect::<Vec<String>>();
+
+        if profiles.is_empty() {
+            profiles.push(String::from(webapps::DEFAULT_SHARED_PROFILE));
+        }
+
         AppEditor {
             app_browser: None,
             app_title: String::new(),
@@ -47,12 +93,53 @@ impl Default for AppEditor {
             app_window_width: String::from(webapps::DEFAULT_WINDOW_WIDTH.to_string()),
             app_window_height: String::from(webapps::DEFAULT_WINDOW_HEIGHT.to_string()),
             app_window_size: webapps::WindowSize::default(),
+            app_remember_window: false,
+            app_download_dir: None,
+            app_allowed_origins: String::new(),
             app_isolated: true,
+            app_shared_profile: profiles[0].clone(),
+            profiles,
             app_simulate_mobile: false,
+            app_device: 0,
+            devices: webapps::device::PRESETS
+                .iter()
+                .map(|device| device.name.to_string())
+                .collect(),
+            app_notifications: false,
+            app_header_bar: false,
+            app_run_in_background: false,
+            app_zoom: webapps::DEFAULT_ZOOM,
+            zoom_presets: webapps::ZOOM_PRESETS
+                .iter()
+                .map(|zoom| format!("{}%", zoom))
+                .collect(),
+            app_user_agent: String::new(),
+            user_agent_presets: webapps::user_agent::PRESETS
+                .iter()
+                .map(|(name, _)| name.to_string())
+                .collect(),
             selected_icon: None,
             categories,
             category_idx: webapps::Category::iter().position(|c| c == Category::Utility),
             is_installed: false,
+            app_comment: String::new(),
+            app_keywords: String::new(),
+            app_mime_types: String::new(),
+            app_startup_notify: false,
+            app_actions: Vec::new(),
+            app_handle_links: false,
+            app_url_handlers: String::new(),
+            app_protocol_handlers: String::new(),
+            app_permissions: SitePermissions::default(),
+            app_user_css: SourceText::default(),
+            app_user_js: SourceText::default(),
+            app_content_blocking: false,
+            app_blocking_allowlist: String::new(),
+            filter_lists_status: None,
+            app_theme_color: None,
+            app_display: None,
+            fetching_details: false,
+            fetch_details_error: None,
         }
     }
 }
@@ -66,11 +153,43 @@ pub enum Message {
     Title(String),
     Url(String),
     WindowWidth(String),
+    RememberWindow(bool),
+    DownloadDir(Option<PathBuf>),
+    DownloadDirPicker,
+    AllowedOrigins(String),
     WindowHeight(String),
     AppIsolated(bool),
+    AppSharedProfile(usize),
     AppSimulateMobile(bool),
+    AppDevice(usize),
+    AppNotifications(bool),
+    AppHeaderBar(bool),
+    AppRunInBackground(bool),
+    AppZoom(usize),
+    UserAgent(String),
+    UserAgentPreset(usize),
     GenerateIcon,
     ResetIcon,
+    Comment(String),
+    Keywords(String),
+    MimeTypes(String),
+    StartupNotify(bool),
+    AddAction,
+    RemoveAction(usize),
+    ActionName(usize, String),
+    ActionUrl(usize, String),
+    HandleLinks(bool),
+    UrlHandlers(String),
+    ProtocolHandlers(String),
+    RevokePermission(String, Permission),
+    UserCss(widget::text_editor::Action),
+    UserJs(widget::text_editor::Action),
+    ContentBlocking(bool),
+    BlockingAllowlist(String),
+    UpdateFilterLists,
+    FilterListsUpdated(String),
+    FetchDetails,
+    DetailsFetched(Result<(SiteDetails, Option<WebappIcon>), String>),
 }
 
 impl AppEditor {
@@ -92,12 +211,72 @@ impl AppEditor {
             editor.app_window_width = window_size.0.to_string();
             editor.app_window_height = window_size.1.to_string();
             editor.app_window_size = window_size.clone();
+            editor.app_remember_window = launcher.browser.remember_window_state.unwrap_or_default();
+            editor.app_download_dir = launcher.browser.download_dir.clone();
+            editor.app_notifications = launcher.browser.notifications.unwrap_or_default();
+            let device = webapps::device::Device::find(launcher.browser.device.as_deref());
+            editor.app_device = webapps::device::PRESETS
+                .iter()
+                .position(|preset| preset == device)
+                .unwrap_or_default();
+            editor.app_header_bar = launcher.browser.header_bar.unwrap_or_default();
+            editor.app_run_in_background = launcher.browser.run_in_background.unwrap_or_default();
+            // zoom chosen in the web app itself may lie between the presets
+            editor.app_zoom = webapps::nearest_zoom_preset(
+                launcher.browser.zoom.unwrap_or(webapps::DEFAULT_ZOOM),
+            );
+            editor.app_user_agent = launcher.browser.user_agent.clone().unwrap_or_default();
+            editor.app_allowed_origins = launcher
+                .browser
+                .allowed_origins
+                .clone()
+                .unwrap_or_default()
+                .join("; ");
+            editor.app_content_blocking = launcher.browser.content_blocking.unwrap_or_default();
+            editor.app_blocking_allowlist = launcher
+                .browser
+                .blocking_allowlist
+                .clone()
+                .unwrap_or_default()
+                .join("; ");
+            editor.app_theme_color = launcher.browser.theme_color.clone();
+            editor.app_display = launcher.browser.display.clone();
             editor.app_simulate_mobile = simulate_mobile;
+            editor.app_isolated = launcher.browser.is_isolated();
+            if let Some(profile) = launcher.browser.shared_profile() {
+                if !editor.profiles.contains(&profile) {
+                    editor.profiles.push(profile.clone());
+                }
+                editor.app_shared_profile = profile;
+            }
             editor.category_idx = editor
                 .categories
                 .iter()
                 .position(|c| c == &launcher.category.name());
             editor.is_installed = true;
+            editor.app_comment = launcher.comment.clone().unwrap_or_default();
+            editor.app_keywords = launcher.keywords.clone().unwrap_or_default().join("; ");
+            editor.app_mime_types = launcher.mime_types.clone().unwrap_or_default().join("; ");
+            editor.app_startup_notify = launcher.startup_notify.unwrap_or_default();
+            editor.app_actions = launcher.actions.clone().unwrap_or_default();
+            if let Some(patterns) = &launcher.browser.url_handlers {
+                editor.app_handle_links = true;
+                editor.app_url_handlers = patterns.join("; ");
+            }
+            editor.app_protocol_handlers = launcher
+                .browser
+                .protocol_handlers
+                .iter()
+                .flatten()
+                .map(|handler| format!("{}={}", handler.protocol, handler.url))
+                .collect::<Vec<String>>()
+                .join("; ");
+
+            editor.app_permissions = SitePermissions::load(&launcher.browser.app_id);
+            editor.app_user_css =
+                SourceText::new(launcher.browser.user_css.as_deref().unwrap_or_default());
+            editor.app_user_js =
+                SourceText::new(launcher.browser.user_js.as_deref().unwrap_or_default());
 
             editor.update_icon(launcher.icon.into());
 
@@ -109,45 +288,209 @@ impl AppEditor {
 
     pub fn update(&mut self, message: Message) -> Task<Action<crate::pages::Message>> {
         match message {
+            Message::ActionName(idx, name) => {
+                self.app_actions[idx].name = name;
+            }
+            Message::ActionUrl(idx, url) => {
+                self.app_actions[idx].url = url;
+            }
+            Message::AddAction => {
+                self.app_actions.push(DesktopAction::default());
+            }
+            Message::Comment(comment) => {
+                self.app_comment = comment;
+            }
+            Message::Keywords(keywords) => {
+                self.app_keywords = keywords;
+            }
+            Message::MimeTypes(mime_types) => {
+                self.app_mime_types = mime_types;
+            }
+            Message::HandleLinks(flag) => {
+                self.app_handle_links = flag;
+
+                if flag && self.app_url_handlers.is_empty() {
+                    self.app_url_handlers =
+                        webapps::url_handler::default_pattern(&self.app_url).unwrap_or_default();
+                }
+            }
+            Message::UrlHandlers(patterns) => {
+                self.app_url_handlers = patterns;
+            }
+            Message::ProtocolHandlers(handlers) => {
+                self.app_protocol_handlers = handlers;
+            }
+            Message::RevokePermission(origin, permission) => {
+                self.app_permissions.revoke(&origin, permission);
+
+                if let Some(browser) = &self.app_browser {
+                    if let Err(e) = self.app_permissions.save(&browser.app_id) {
+                        eprintln!("Failed to save permissions: {}", e);
+                    }
+                }
+            }
+            Message::UserCss(action) => {
+                self.app_user_css.0.perform(action);
+            }
+            Message::UserJs(action) => {
+                self.app_user_js.0.perform(action);
+            }
+            Message::ContentBlocking(flag) => {
+                self.app_content_blocking = flag;
+            }
+            Message::BlockingAllowlist(hosts) => {
+                self.app_blocking_allowlist = hosts;
+            }
+            Message::UpdateFilterLists => {
+                self.filter_lists_status = Some(fl!("filter-lists-updating"));
+
+                return task::future(async {
+                    let result = tokio::task::spawn_blocking(webapps::content_filter::update_lists)
+                        .await
+                        .map_err(anyhow::Error::from)
+                        .and_then(|result| result);
+
+                    let status = match result {
+                        Ok(count) => fl!("filter-lists-updated", count = count),
+                        Err(e) => format!("{}: {}", fl!("filter-lists-failed"), e),
+                    };
+
+                    cosmic::action::app(pages::Message::Editor(Message::FilterListsUpdated(status)))
+                });
+            }
+            Message::FilterListsUpdated(status) => {
+                self.filter_lists_status = Some(status);
+            }
+            Message::RemoveAction(idx) => {
+                self.app_actions.remove(idx);
+            }
+            Message::StartupNotify(flag) => {
+                self.app_startup_notify = flag;
+            }
             Message::AppIsolated(flag) => {
                 self.app_isolated = flag;
             }
+            Message::AppSharedProfile(idx) => {
+                self.app_shared_profile = self.profiles[idx].clone();
+            }
+            Message::AppNotifications(flag) => {
+                self.app_notifications = flag;
+            }
+            Message::AppHeaderBar(flag) => {
+                self.app_header_bar = flag;
+            }
+            Message::AppRunInBackground(flag) => {
+                self.app_run_in_background = flag;
+            }
+            Message::AppZoom(idx) => {
+                self.app_zoom = webapps::ZOOM_PRESETS[idx];
+            }
+            Message::UserAgent(user_agent) => {
+                self.app_user_agent = user_agent;
+            }
+            Message::UserAgentPreset(idx) => {
+                self.app_user_agent = webapps::user_agent::PRESETS[idx].1.to_string();
+            }
             Message::AppSimulateMobile(flag) => {
                 self.app_simulate_mobile = flag;
+
+                if flag {
+                    self.apply_device_size();
+                }
+            }
+            Message::AppDevice(idx) => {
+                self.app_device = idx;
+                self.apply_device_size();
             }
             Message::Category(idx) => {
                 self.app_category = webapps::Category::from_index(idx as u8);
                 self.category_idx = Some(idx);
             }
             Message::Done => {
-                let browser = if let Some(browser) = &self.app_browser {
+                let mut browser = if let Some(browser) = &self.app_browser {
                     browser.clone()
                 } else {
-                    let app_id = self.app_title.replace(' ', "");
-                    let app_id = app_id + &rng().random_range(1000..10000).to_string();
-
-                    let mut browser = webapps::browser::Browser::new(&app_id);
-                    browser.window_title = Some(self.app_title.clone());
-                    browser.url = Some(self.app_url.clone());
-                    browser.window_size = Some(self.app_window_size.clone());
-                    browser.try_simulate_mobile = Some(self.app_simulate_mobile);
-                    browser
+                    let app_id = webapps::browser::app_id_from_name(&self.app_title);
+
+                    webapps::browser::Browser::new(&app_id)
                 };
 
+                browser.window_title = Some(self.app_title.clone());
+                browser.url = Some(self.app_url.clone());
+                browser.window_size = Some(self.app_window_size.clone());
+                browser.remember_window_state = Some(self.app_remember_window);
+                browser.download_dir = self.app_download_dir.clone();
+                browser.notifications = Some(self.app_notifications);
+                browser.header_bar = Some(self.app_header_bar);
+                browser.run_in_background = Some(self.app_run_in_background);
+                browser.zoom = Some(self.app_zoom);
+                browser.user_agent = Some(self.app_user_agent.trim().to_string())
+                    .filter(|user_agent| !user_agent.is_empty());
+                browser.allowed_origins = Some(split_list(&self.app_allowed_origins));
+                browser.try_simulate_mobile = Some(self.app_simulate_mobile);
+                browser.device = Some(webapps::device::PRESETS[self.app_device].name.to_string());
+                browser.user_css = self.app_user_css.value();
+                browser.user_js = self.app_user_js.value();
+                browser.content_blocking = Some(self.app_content_blocking);
+                browser.theme_color = self.app_theme_color.clone();
+                browser.display = self.app_display.clone();
+                browser.blocking_allowlist = Some(split_list(&self.app_blocking_allowlist));
+                browser.url_handlers = if self.app_handle_links {
+                    // links of other schemes go through protocol handlers
+                    let patterns = split_list(&self.app_url_handlers)
+                        .into_iter()
+                        .filter(|pattern| {
+                            pattern.starts_with("http://") || pattern.starts_with("https://")
+                        })
+                        .collect::<Vec<String>>();
+
+                    if patterns.is_empty() {
+                        webapps::url_handler::default_pattern(&self.app_url).map(|p| vec![p])
+                    } else {
+                        Some(patterns)
+                    }
+                } else {
+                    None
+                };
+                let protocol_handlers = self
+                    .app_protocol_handlers
+                    .split(';')
+                    .filter_map(ProtocolHandler::parse)
+                    .collect::<Vec<ProtocolHandler>>();
+                browser.protocol_handlers =
+                    (!protocol_handlers.is_empty()).then_some(protocol_handlers);
+
+                browser.set_profile(self.app_isolated, &self.app_shared_profile);
+
                 if webapps::launcher::webapplauncher_is_valid(&self.app_title, &browser.url) {
                     if let Some(icon) = &self.app_icon {
-                        let launcher = webapps::launcher::WebAppLauncher {
-                            browser: browser.clone(),
-                            name: self.app_title.clone(),
-                            icon: icon.clone(),
-                            category: self.app_category.clone(),
-                        };
+                        let mut launcher = webapps::launcher::WebAppLauncher::new(
+                            browser.clone(),
+                            self.app_title.clone(),
+                            icon.clone(),
+                            self.app_category.clone(),
+                        );
+                        launcher.comment =
+                            Some(self.app_comment.trim().to_string()).filter(|c| !c.is_empty());
+                        launcher.keywords = Some(split_list(&self.app_keywords));
+                        launcher.mime_types = Some(split_list(&self.app_mime_types));
+                        launcher.startup_notify = Some(self.app_startup_notify);
+                        launcher.actions = Some(
+                            self.app_actions
+                                .iter()
+                                .filter(|action| {
+                                    !action.name.trim().is_empty()
+                                        && webapps::url_valid(&action.url)
+                                })
+                                .cloned()
+                                .collect(),
+                        );
 
                         return task::future(async move {
-                            if let Ok(success) = launcher.create().await {
-                                if success {
-                                    return crate::pages::Message::SaveLauncher(launcher);
-                                }
+                            match launcher.create().await {
+                                Ok(true) => return crate::pages::Message::SaveLauncher(launcher),
+                                Ok(false) => {}
+                                Err(e) => tracing::error!("failed to install web app: {e:#}"),
                             }
                             crate::pages::Message::None
                         });
@@ -193,6 +536,100 @@ impl AppEditor {
             Message::Url(url) => {
                 self.app_url = url;
             }
+            Message::FetchDetails => {
+                self.fetching_details = true;
+                self.fetch_details_error = None;
+
+                let url = self.app_url.clone();
+
+                return task::future(async move {
+                    let result = tokio::task::spawn_blocking(move || {
+                        let details = webapps::manifest::discover(&url)?;
+                        let icon = details.icon_url.as_deref().and_then(|icon_url| {
+                            webapps::manifest::save_icon(icon_url)
+                                .inspect_err(|e| eprintln!("Failed to save {}: {}", icon_url, e))
+                                .ok()
+                        });
+
+                        anyhow::Ok((details, icon))
+                    })
+                    .await
+                    .map_err(anyhow::Error::from)
+                    .and_then(|result| result)
+                    .map_err(|e| e.to_string());
+
+                    cosmic::action::app(pages::Message::Editor(Message::DetailsFetched(result)))
+                });
+            }
+            Message::DetailsFetched(result) => {
+                self.fetching_details = false;
+
+                match result {
+                    Ok((details, icon)) => {
+                        if let Some(name) = details.name {
+                            self.app_title = name;
+                        }
+                        if let Some(start_url) = details.start_url {
+                            self.app_url = start_url;
+                        }
+                        if let Some(category) = details.category {
+                            self.category_idx =
+                                webapps::Category::iter().position(|c| c == category);
+                            self.app_category = category;
+                        }
+                        if let Some(scope) = details.scope {
+                            if self.app_url_handlers.is_empty() {
+                                self.app_url_handlers = format!("{}*", scope);
+                            }
+                        }
+                        self.app_theme_color = details.theme_color;
+                        self.app_display = details.display;
+                        self.update_icon(icon);
+                    }
+                    Err(e) => self.fetch_details_error = Some(e),
+                }
+            }
+            Message::AllowedOrigins(origins) => {
+                self.app_allowed_origins = origins;
+            }
+            Message::DownloadDir(path) => {
+                self.app_download_dir = path;
+            }
+            Message::DownloadDirPicker => {
+                return task::future(async {
+                    let result = SelectedFiles::open_file()
+                        .title("Download Folder")
+                        .accept_label("Select")
+                        .modal(true)
+                        .directory(true)
+                        .send()
+                        .await
+                        .unwrap()
+                        .response();
+
+                    if let Ok(result) = result {
+                        if let Some(folder) = result.uris().first() {
+                            let path = urlencoding::decode(
+                                folder
+                                    .as_str()
+                                    .strip_prefix("file://")
+                                    .unwrap_or(folder.as_str()),
+                            )
+                            .unwrap_or_default()
+                            .to_string();
+
+                            return cosmic::action::app(pages::Message::Editor(
+                                Message::DownloadDir(Some(PathBuf::from(path))),
+                            ));
+                        }
+                    }
+
+                    cosmic::action::none()
+                });
+            }
+            Message::RememberWindow(flag) => {
+                self.app_remember_window = flag;
+            }
             Message::WindowWidth(width) => {
                 self.app_window_width = width;
                 self.app_window_size.0 = self.app_window_width.parse().unwrap_or_default();
@@ -212,6 +649,15 @@ impl AppEditor {
         }
     }
 
+    /// The window takes the size of the emulated device.
+    fn apply_device_size(&mut self) {
+        let window_size = webapps::device::PRESETS[self.app_device].window_size();
+
+        self.app_window_width = window_size.0.to_string();
+        self.app_window_height = window_size.1.to_string();
+        self.app_window_size = window_size;
+    }
+
     fn icon_element(&self, icon: Option<webapps::Icon>) -> Element<'_, Message> {
         let ico = if let Some(ico) = icon {
             match ico.icon {
@@ -233,8 +679,166 @@ impl AppEditor {
         widget::container(ico).into()
     }
 
+    fn desktop_entry_section(&self) -> Element<'_, Message> {
+        let mut section = widget::settings::section()
+            .title(fl!("desktop-entry"))
+            .add(widget::settings::item(
+                fl!("comment"),
+                widget::text_input(fl!("comment"), &self.app_comment).on_input(Message::Comment),
+            ))
+            .add(widget::settings::item(
+                fl!("keywords"),
+                widget::text_input("Mail; Calendar", &self.app_keywords)
+                    .on_input(Message::Keywords),
+            ))
+            .add(widget::settings::item(
+                fl!("mime-types"),
+                widget::text_input("x-scheme-handler/mailto", &self.app_mime_types)
+                    .on_input(Message::MimeTypes),
+            ))
+            .add(widget::settings::item(
+                fl!("handle-links"),
+                widget::toggler(self.app_handle_links).on_toggle(Message::HandleLinks),
+            ))
+            .add_maybe(self.app_handle_links.then(|| {
+                widget::settings::item(
+                    fl!("url-patterns"),
+                    widget::text_input("https://mail.example.com/*", &self.app_url_handlers)
+                        .on_input(Message::UrlHandlers),
+                )
+            }))
+            .add(widget::settings::item(
+                fl!("protocol-handlers"),
+                widget::text_input(
+                    "mailto=https://mail.example.com/compose?to=%s",
+                    &self.app_protocol_handlers,
+                )
+                .on_input(Message::ProtocolHandlers),
+            ))
+            .add(widget::settings::item(
+                fl!("startup-notify"),
+                widget::toggler(self.app_startup_notify).on_toggle(Message::StartupNotify),
+            ));
+
+        for (idx, action) in self.app_actions.iter().enumerate() {
+            section = section.add(
+                widget::row()
+                    .spacing(8)
+                    .push(
+                        widget::text_input(fl!("action-name"), &action.name)
+                            .on_input(move |name| Message::ActionName(idx, name)),
+                    )
+                    .push(
+                        widget::text_input(fl!("url"), &action.url)
+                            .on_input(move |url| Message::ActionUrl(idx, url)),
+                    )
+                    .push(
+                        widget::button::icon(widget::icon::from_name("edit-delete-symbolic"))
+                            .on_press(Message::RemoveAction(idx)),
+                    ),
+            );
+        }
+
+        section
+            .add(widget::settings::item(
+                fl!("desktop-actions"),
+                widget::button::standard(fl!("add-action")).on_press(Message::AddAction),
+            ))
+            .into()
+    }
+
+    fn content_blocking_section(&self) -> Element<'_, Message> {
+        widget::settings::section()
+            .title(fl!("content-blocking"))
+            .add(widget::settings::item(
+                fl!("block-content"),
+                widget::toggler(self.app_content_blocking).on_toggle(Message::ContentBlocking),
+            ))
+            .add_maybe(self.app_content_blocking.then(|| {
+                widget::settings::item(
+                    fl!("blocking-allowlist"),
+                    widget::text_input("example.com", &self.app_blocking_allowlist)
+                        .on_input(Message::BlockingAllowlist),
+                )
+            }))
+            .add(widget::settings::item(
+                fl!("filter-lists"),
+                widget::row()
+                    .spacing(8)
+                    .align_y(Vertical::Center)
+                    .push_maybe(self.filter_lists_status.clone().map(widget::text::body))
+                    .push(
+                        widget::button::standard(fl!("update-filter-lists"))
+                            .on_press(Message::UpdateFilterLists),
+                    ),
+            ))
+            .into()
+    }
+
+    fn user_content_section(&self) -> Element<'_, Message> {
+        widget::settings::section()
+            .title(fl!("user-content"))
+            .add(
+                widget::column()
+                    .spacing(8)
+                    .push(widget::text::body(fl!("user-css")))
+                    .push(
+                        widget::text_editor(&self.app_user_css.0)
+                            .placeholder("header { display: none; }")
+                            .height(120.)
+                            .on_action(Message::UserCss),
+                    ),
+            )
+            .add(
+                widget::column()
+                    .spacing(8)
+                    .push(widget::text::body(fl!("user-js")))
+                    .push(
+                        widget::text_editor(&self.app_user_js.0)
+                            .placeholder("document.title = \"Mail\";")
+                            .height(120.)
+                            .on_action(Message::UserJs),
+                    ),
+            )
+            .into()
+    }
+
+    fn permissions_section(&self) -> Element<'_, Message> {
+        let mut section = widget::settings::section().title(fl!("site-permissions"));
+
+        if self.app_permissions.sites.is_empty() {
+            section = section.add(widget::settings::item(
+                fl!("no-site-permissions"),
+                widget::horizontal_space(),
+            ));
+        }
+
+        for (origin, permissions) in &self.app_permissions.sites {
+            for (permission, decision) in permissions {
+                let decision = match decision {
+                    Decision::Allow => fl!("permission-allowed"),
+                    Decision::Deny => fl!("permission-denied"),
+                };
+
+                section = section.add(widget::settings::item(
+                    format!("{} – {}", origin, permission.name()),
+                    widget::row()
+                        .spacing(8)
+                        .align_y(Vertical::Center)
+                        .push(widget::text::body(decision))
+                        .push(
+                            widget::button::standard(fl!("revoke"))
+                                .on_press(Message::RevokePermission(origin.clone(), *permission)),
+                        ),
+                ));
+            }
+        }
+
+        section.into()
+    }
+
     pub fn view(&self) -> Element<'_, Message> {
-        widget::container(
+        widget::container(widget::scrollable(
             widget::column()
                 .spacing(24)
                 .push(
@@ -302,7 +906,25 @@ impl AppEditor {
                             },
                         )),
                 )
-                .push(widget::text_input(fl!("url"), &self.app_url).on_input(Message::Url))
+                .push(
+                    widget::row()
+                        .spacing(8)
+                        .push(widget::text_input(fl!("url"), &self.app_url).on_input(Message::Url))
+                        .push(
+                            widget::button::standard(if self.fetching_details {
+                                fl!("fetching-details")
+                            } else {
+                                fl!("fetch-details")
+                            })
+                            .on_press_maybe(
+                                (!self.fetching_details && webapps::url_valid(&self.app_url))
+                                    .then_some(Message::FetchDetails),
+                            ),
+                        ),
+                )
+                .push_maybe(self.fetch_details_error.as_ref().map(|e| {
+                    widget::text::caption(format!("{}: {}", fl!("fetch-details-failed"), e))
+                }))
                 .push(
                     widget::settings::section()
                         .add(widget::settings::item(
@@ -314,34 +936,139 @@ impl AppEditor {
                             ),
                         ))
                         .add(widget::settings::item(
-                            fl!("window-size"),
+                            fl!("remember-window"),
+                            widget::toggler(self.app_remember_window)
+                                .on_toggle(Message::RememberWindow),
+                        ))
+                        .add_maybe((!self.app_remember_window).then(|| {
+                            widget::settings::item(
+                                fl!("window-size"),
+                                widget::row()
+                                    .spacing(8)
+                                    .push(
+                                        widget::text_input(
+                                            format!("{}", webapps::DEFAULT_WINDOW_WIDTH),
+                                            &self.app_window_width,
+                                        )
+                                        .on_input(Message::WindowWidth),
+                                    )
+                                    .push(
+                                        widget::text_input(
+                                            format!("{}", webapps::DEFAULT_WINDOW_HEIGHT),
+                                            &self.app_window_height,
+                                        )
+                                        .on_input(Message::WindowHeight),
+                                    ),
+                            )
+                        }))
+                        .add(widget::settings::item(
+                            fl!("default-zoom"),
+                            widget::dropdown(
+                                &self.zoom_presets,
+                                webapps::ZOOM_PRESETS
+                                    .iter()
+                                    .position(|zoom| *zoom == self.app_zoom),
+                                Message::AppZoom,
+                            ),
+                        ))
+                        .add(widget::settings::item(
+                            fl!("allowed-origins"),
+                            widget::text_input(
+                                "https://accounts.example.com",
+                                &self.app_allowed_origins,
+                            )
+                            .on_input(Message::AllowedOrigins),
+                        ))
+                        .add(widget::settings::item(
+                            fl!("download-dir"),
                             widget::row()
                                 .spacing(8)
+                                .align_y(Vertical::Center)
+                                .push(widget::text::body(
+                                    self.app_download_dir
+                                        .clone()
+                                        .or_else(dirs::download_dir)
+                                        .unwrap_or_default()
+                                        .display()
+                                        .to_string(),
+                                ))
                                 .push(
-                                    widget::text_input(
-                                        format!("{}", webapps::DEFAULT_WINDOW_WIDTH),
-                                        &self.app_window_width,
-                                    )
-                                    .on_input(Message::WindowWidth),
+                                    widget::button::standard(fl!("change"))
+                                        .on_press(Message::DownloadDirPicker),
                                 )
-                                .push(
-                                    widget::text_input(
-                                        format!("{}", webapps::DEFAULT_WINDOW_HEIGHT),
-                                        &self.app_window_height,
-                                    )
-                                    .on_input(Message::WindowHeight),
-                                ),
+                                .push_maybe(self.app_download_dir.is_some().then(|| {
+                                    widget::button::icon(widget::icon::from_name(
+                                        "edit-clear-symbolic",
+                                    ))
+                                    .on_press(Message::DownloadDir(None))
+                                })),
                         ))
                         .add(widget::settings::item(
                             fl!("isolated-profile"),
                             widget::toggler(self.app_isolated).on_toggle(Message::AppIsolated),
                         ))
+                        .add_maybe(if self.app_isolated {
+                            None
+                        } else {
+                            Some(widget::settings::item(
+                                fl!("shared-profile"),
+                                widget::dropdown(
+                                    &self.profiles,
+                                    self.profiles
+                                        .iter()
+                                        .position(|p| p == &self.app_shared_profile),
+                                    Message::AppSharedProfile,
+                                ),
+                            ))
+                        })
+                        .add(widget::settings::item(
+                            fl!("notifications"),
+                            widget::toggler(self.app_notifications)
+                                .on_toggle(Message::AppNotifications),
+                        ))
+                        .add(widget::settings::item(
+                            fl!("header-bar"),
+                            widget::toggler(self.app_header_bar).on_toggle(Message::AppHeaderBar),
+                        ))
+                        .add(widget::settings::item(
+                            fl!("run-in-background"),
+                            widget::toggler(self.app_run_in_background)
+                                .on_toggle(Message::AppRunInBackground),
+                        ))
                         .add(widget::settings::item(
                             fl!("simulate-mobile"),
                             widget::toggler(self.app_simulate_mobile)
                                 .on_toggle(Message::AppSimulateMobile),
-                        )),
+                        ))
+                        .add_maybe(self.app_simulate_mobile.then(|| {
+                            widget::settings::item(
+                                fl!("device"),
+                                widget::dropdown(
+                                    &self.devices,
+                                    Some(self.app_device),
+                                    Message::AppDevice,
+                                ),
+                            )
+                        }))
+                        .add(widget::settings::item(
+                            fl!("user-agent"),
+                            widget::dropdown(
+                                &self.user_agent_presets,
+                                webapps::user_agent::PRESETS
+                                    .iter()
+                                    .position(|(_, user_agent)| *user_agent == self.app_user_agent),
+                                Message::UserAgentPreset,
+                            ),
+                        ))
+                        .add(
+                            widget::text_input(fl!("default-user-agent"), &self.app_user_agent)
+                                .on_input(Message::UserAgent),
+                        ),
                 )
+                .push(self.desktop_entry_section())
+                .push(self.content_blocking_section())
+                .push(self.user_content_section())
+                .push_maybe(self.is_installed.then(|| self.permissions_section()))
                 .push(
                     widget::row()
                         .spacing(8)
@@ -365,9 +1092,53 @@ impl AppEditor {
                             },
                         )),
                 ),
-        )
+        ))
         .padding(cosmic::iced::Padding::new(0.).left(30.0).right(30.0))
         .max_width(1000)
         .into()
     }
 }
+
+/// Contents of a multi-line editor, which has to be cloned along with the page.
+pub struct SourceText(pub widget::text_editor::Content);
+
+impl SourceText {
+    pub fn new(text: &str) -> Self {
+        Self(widget::text_editor::Content::with_text(text))
+    }
+
+    /// The edited text, `None` when there's nothing but whitespace.
+    pub fn value(&self) -> Option<String> {
+        let text = self.0.text();
+
+        (!text.trim().is_empty()).then_some(text)
+    }
+}
+
+impl Default for SourceText {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl Clone for SourceText {
+    fn clone(&self) -> Self {
+        Self::new(&self.0.text())
+    }
+}
+
+impl std::fmt::Debug for SourceText {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SourceText").field(&self.0.text()).finish()
+    }
+}
+
+/// Splits user input like `Mail; Calendar` into list values.
+fn split_list(input: &str) -> Vec<String> {
+    input
+        .split([';', ','])
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(String::from)
+        .collect()
+}
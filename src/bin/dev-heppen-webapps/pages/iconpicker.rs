@@ -6,7 +6,7 @@ use cosmic::{
     task, theme,
     widget::{self},
 };
-use webapps::fl;
+use webapps::{favicon::IconCandidate, fl};
 
 use crate::pages;
 
@@ -17,12 +17,20 @@ pub enum Message {
     OpenIconPickerDialog,
     IconSearch,
     SetIcon(Option<webapps::Icon>),
+    FetchSiteIcons,
+    SiteIconsFetched(Result<Vec<IconCandidate>, String>),
+    PickSiteIcon(IconCandidate),
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct IconPicker {
     pub icon_searching: String,
     pub icons: Vec<webapps::Icon>,
+    /// URL entered in the editor, its site is asked for icons
+    pub site_url: String,
+    pub site_icons: Vec<IconCandidate>,
+    pub fetching_site_icons: bool,
+    pub site_icons_error: Option<String>,
 }
 
 impl IconPicker {
@@ -83,6 +91,45 @@ impl IconPicker {
             Message::SetIcon(icon) => {
                 return task::message(pages::Message::SetIcon(icon));
             }
+            Message::FetchSiteIcons => {
+                self.fetching_site_icons = true;
+                self.site_icons_error = None;
+
+                let url = self.site_url.clone();
+
+                return task::future(async move {
+                    let result = tokio::task::spawn_blocking(move || {
+                        webapps::favicon::fetch_candidates(&url)
+                    })
+                    .await
+                    .map_err(anyhow::Error::from)
+                    .and_then(|result| result)
+                    .map_err(|e| e.to_string());
+
+                    cosmic::action::app(pages::Message::IconPicker(Message::SiteIconsFetched(
+                        result,
+                    )))
+                });
+            }
+            Message::SiteIconsFetched(result) => {
+                self.fetching_site_icons = false;
+
+                match result {
+                    Ok(candidates) if candidates.is_empty() => {
+                        self.site_icons_error = Some(fl!("no-site-icons"));
+                    }
+                    Ok(candidates) => self.site_icons = candidates,
+                    Err(e) => self.site_icons_error = Some(e),
+                }
+            }
+            Message::PickSiteIcon(candidate) => match webapps::favicon::keep(&candidate) {
+                Ok(icon) => {
+                    let icon = webapps::handle_icon(icon.path);
+
+                    return task::message(pages::Message::SetIcon(Some(icon)));
+                }
+                Err(e) => self.site_icons_error = Some(e.to_string()),
+            },
         }
 
         Task::none()
@@ -107,6 +154,45 @@ impl IconPicker {
             icons.push(btn.into());
         }
 
+        // shown at their native size, so blurry upscaled favicons are easy to spot
+        let site_icons = self
+            .site_icons
+            .iter()
+            .map(|candidate| {
+                let width = candidate.width.clamp(16, 256) as f32;
+                let height = candidate.height.clamp(16, 256) as f32;
+
+                let image: Element<Message> = if candidate.scalable {
+                    widget::svg(widget::svg::Handle::from_path(&candidate.path))
+                        .width(Length::Fixed(width))
+                        .height(Length::Fixed(height))
+                        .into()
+                } else {
+                    widget::image(widget::image::Handle::from_path(&candidate.path))
+                        .width(Length::Fixed(width))
+                        .height(Length::Fixed(height))
+                        .into()
+                };
+
+                let size = if candidate.scalable {
+                    String::from("SVG")
+                } else {
+                    format!("{}×{}", candidate.width, candidate.height)
+                };
+
+                widget::button::custom(
+                    widget::column()
+                        .spacing(4)
+                        .align_x(cosmic::iced::Alignment::Center)
+                        .push(image)
+                        .push(widget::text::caption(size)),
+                )
+                .on_press(Message::PickSiteIcon(candidate.clone()))
+                .class(theme::Button::Icon)
+                .into()
+            })
+            .collect::<Vec<Element<Message>>>();
+
         let icons_input = widget::text_input(fl!("icon-name-to-find"), &self.icon_searching)
             .on_input(Message::CustomIconsSearch)
             .on_submit(|_| Message::IconSearch);
@@ -120,6 +206,17 @@ impl IconPicker {
                         .spacing(8)
                         .push(icons_input)
                         .push(button)
+                        .push(
+                            widget::button::standard(if self.fetching_site_icons {
+                                fl!("fetching-details")
+                            } else {
+                                fl!("download-favicon")
+                            })
+                            .on_press_maybe(
+                                (!self.fetching_site_icons && webapps::url_valid(&self.site_url))
+                                    .then_some(Message::FetchSiteIcons),
+                            ),
+                        )
                         .push_maybe(if !webapps::icon_pack_installed() {
                             Some(
                                 widget::button::standard(fl!("download"))
@@ -131,6 +228,11 @@ impl IconPicker {
                 )
                 .padding(8),
             )
+            .push_maybe(self.site_icons_error.as_ref().map(widget::text::caption))
+            .push_maybe((!site_icons.is_empty()).then(|| {
+                widget::container(widget::scrollable(widget::flex_row(site_icons).spacing(8)))
+                    .height(Length::FillPortion(1))
+            }))
             .push_maybe(if !icons.is_empty() {
                 Some(
                     widget::container(widget::scrollable(widget::flex_row(icons)))
@@ -1,7 +1,12 @@
 pub mod editor;
 mod iconpicker;
+mod profiles;
 
-use crate::{config::AppConfig, pages::iconpicker::IconPicker, themes::Theme};
+use crate::{
+    config::AppConfig,
+    pages::{iconpicker::IconPicker, profiles::ProfileManager},
+    themes::Theme,
+};
 use ashpd::desktop::file_chooser::{FileFilter, SelectedFiles};
 use cosmic::{
     Application, Element,
@@ -21,11 +26,10 @@ use cosmic::{
     },
 };
 use editor::AppEditor;
-use ron::ser::to_string_pretty;
 use std::{
     collections::HashMap,
     fs::read_dir,
-    io::{Read, Write},
+    io::Read,
     path::Path,
     process::ExitStatus,
     str::FromStr,
@@ -38,7 +42,12 @@ use tokio::{
     sync::oneshot,
 };
 use tracing::debug;
-use webapps::{APP_ICON, APP_ID, REPOSITORY, fl};
+use webapps::{
+    APP_ICON, APP_ID, REPOSITORY,
+    bundle::{BUNDLE_EXTENSION, Bundle},
+    fl,
+    launcher::InvalidRecord,
+};
 
 static MENU_ID: LazyLock<cosmic::widget::Id> =
     LazyLock::new(|| cosmic::widget::Id::new("responsive-menu"));
@@ -57,6 +66,11 @@ pub enum Message {
     Close,
     IconPicker(iconpicker::Message),
     IconsResult(Vec<String>),
+    ExportBundle(String),
+    ExportBundleFilePicker,
+    ImportBundle(String),
+    ImportBundleFilePicker,
+    InstallBundle(Bundle),
     ImportThemeFilePicker,
     Launch(webapps::WebviewArgs),
     LaunchUrl(String),
@@ -66,6 +80,7 @@ pub enum Message {
     OpenRepositoryUrl,
     OpenThemeResult(String),
     ConfirmDeletion(widget::segmented_button::Entity),
+    Profiles(profiles::Message),
     PushIcon(webapps::Icon),
     ReloadNavbarItems,
     ResetSettings,
@@ -83,6 +98,7 @@ pub enum Message {
 #[derive(Debug, Clone)]
 pub enum Page {
     Editor(AppEditor),
+    Profiles(ProfileManager),
 }
 
 #[derive(Debug, Clone)]
@@ -90,6 +106,8 @@ pub enum Dialogs {
     IconPicker(IconPicker),
     Confirmation((widget::segmented_button::Entity, String)),
     IconsDownloader,
+    InvalidRecords(Vec<InvalidRecord>),
+    ImportScripts(Bundle),
 }
 
 pub struct QuickWebApps {
@@ -105,6 +123,7 @@ pub struct QuickWebApps {
     downloader_output: String,
     themes_list: Vec<Theme>,
     theme_idx: Option<usize>,
+    invalid_records: Vec<InvalidRecord>,
 }
 
 impl Application for QuickWebApps {
@@ -142,6 +161,7 @@ impl Application for QuickWebApps {
             downloader_output: String::new(),
             themes_list,
             theme_idx: Some(0),
+            invalid_records: Vec::new(),
         };
 
         let tasks = vec![
@@ -222,31 +242,32 @@ impl Application for QuickWebApps {
             Message::ConfirmDeletion(id) => {
                 let data = self.nav.data::<Page>(id);
 
-                if let Some(page) = data {
-                    let Page::Editor(app_editor) = page;
+                if let Some(Page::Editor(app_editor)) = data {
                     self.dialogs = Some(Dialogs::Confirmation((id, app_editor.app_title.clone())))
                 };
             }
-            Message::Editor(msg) => match &mut self.page {
-                Page::Editor(app_editor) => tasks.push(app_editor.update(msg)),
-            },
+            Message::Editor(msg) => {
+                if let Page::Editor(app_editor) = &mut self.page {
+                    tasks.push(app_editor.update(msg))
+                }
+            }
             Message::Delete(id) => {
                 let data = self.nav.data::<Page>(id);
 
-                if let Some(page) = data {
-                    let Page::Editor(app_editor) = page;
-
+                if let Some(Page::Editor(app_editor)) = data {
                     if let Some(browser) = &app_editor.app_browser {
                         if let Some(icon) = &app_editor.app_icon {
-                            let launcher = webapps::launcher::WebAppLauncher {
-                                browser: browser.clone(),
-                                name: app_editor.app_title.clone(),
-                                icon: icon.clone(),
-                                category: app_editor.app_category.clone(),
-                            };
+                            let launcher = webapps::launcher::WebAppLauncher::new(
+                                browser.clone(),
+                                app_editor.app_title.clone(),
+                                icon.clone(),
+                                app_editor.app_category.clone(),
+                            );
 
                             return task::future(async move {
-                                launcher.delete().await.unwrap();
+                                if let Err(e) = launcher.delete().await {
+                                    tracing::error!("failed to delete web app: {e:#}");
+                                }
                                 cosmic::action::app(Message::DeletionDone(id))
                             });
                         }
@@ -308,6 +329,84 @@ impl Application for QuickWebApps {
                     }
                 };
             }
+            Message::ExportBundle(path) => {
+                let launchers = webapps::launcher::installed_webapps();
+
+                if let Err(e) = Bundle::new(&launchers).export(Path::new(&path)) {
+                    tracing::error!("failed to export web apps: {e}");
+                }
+            }
+            Message::ExportBundleFilePicker => {
+                return task::future(async {
+                    let result = SelectedFiles::save_file()
+                        .title("Export Web Apps")
+                        .accept_label("Export")
+                        .modal(true)
+                        .current_name(format!("export.{BUNDLE_EXTENSION}").as_str())
+                        .filter(FileFilter::new("Web Apps Bundle").glob("*.ron"))
+                        .send()
+                        .await
+                        .unwrap()
+                        .response();
+
+                    if let Ok(result) = result {
+                        if let Some(file) = result.uris().first() {
+                            return cosmic::action::app(Message::ExportBundle(file_uri_path(
+                                file.as_str(),
+                            )));
+                        }
+                    }
+
+                    cosmic::action::none()
+                });
+            }
+            Message::ImportBundle(path) => match Bundle::load(Path::new(&path)) {
+                // custom code runs with the rights of the page, the user gets to read it first
+                Ok(bundle) if !bundle.apps_with_custom_code().is_empty() => {
+                    self.dialogs = Some(Dialogs::ImportScripts(bundle));
+                }
+                Ok(bundle) => {
+                    return task::message(cosmic::action::app(Message::InstallBundle(bundle)));
+                }
+                Err(e) => tracing::error!("failed to load bundle: {e}"),
+            },
+            Message::InstallBundle(bundle) => {
+                self.dialogs = None;
+
+                return task::future(async move {
+                    for result in bundle.import().await {
+                        if let Err(e) = result {
+                            tracing::error!("failed to import web app: {e}");
+                        }
+                    }
+
+                    cosmic::action::app(Message::ReloadNavbarItems)
+                });
+            }
+            Message::ImportBundleFilePicker => {
+                return task::future(async {
+                    let result = SelectedFiles::open_file()
+                        .title("Import Web Apps")
+                        .accept_label("Import")
+                        .modal(true)
+                        .multiple(false)
+                        .filter(FileFilter::new("Web Apps Bundle").glob("*.ron"))
+                        .send()
+                        .await
+                        .unwrap()
+                        .response();
+
+                    if let Ok(result) = result {
+                        if let Some(file) = result.uris().first() {
+                            return cosmic::action::app(Message::ImportBundle(file_uri_path(
+                                file.as_str(),
+                            )));
+                        }
+                    }
+
+                    cosmic::action::none()
+                });
+            }
             Message::ImportThemeFilePicker => {
                 return task::future(async {
                     let result = SelectedFiles::open_file()
@@ -423,7 +522,13 @@ impl Application for QuickWebApps {
                 self.dialogs = None;
             }
             Message::OpenIconPicker => {
-                self.dialogs = Some(Dialogs::IconPicker(IconPicker::default()));
+                let mut icon_picker = IconPicker::default();
+
+                if let Page::Editor(app_editor) = &self.page {
+                    icon_picker.site_url = app_editor.app_url.clone();
+                }
+
+                self.dialogs = Some(Dialogs::IconPicker(icon_picker));
             }
             Message::OpenRepositoryUrl => {
                 _ = open::that_detached(REPOSITORY);
@@ -444,12 +549,19 @@ impl Application for QuickWebApps {
 
                 tasks.push(task::message(Message::LoadThemes));
             }
+            Message::Profiles(msg) => {
+                if let Page::Profiles(manager) = &mut self.page {
+                    tasks.push(manager.update(msg))
+                }
+            }
             Message::PushIcon(icon) => {
                 if let Some(Dialogs::IconPicker(icon_picker)) = &mut self.dialogs {
                     icon_picker.push_icon(icon);
                 }
             }
             Message::ReloadNavbarItems => {
+                let on_profiles = matches!(self.page, Page::Profiles(_));
+
                 self.nav.clear();
 
                 self.nav
@@ -459,20 +571,39 @@ impl Application for QuickWebApps {
                     .data::<Page>(Page::Editor(AppEditor::default()))
                     .activate();
 
-                webapps::launcher::installed_webapps()
-                    .into_iter()
-                    .for_each(|app| {
-                        self.nav
-                            .insert()
-                            .icon(navbar_item_icon(
-                                &app.icon.path.as_path().to_str().expect("path conversion"),
-                            ))
-                            .text(app.name.clone())
-                            .data::<Page>(Page::Editor(editor::AppEditor::from(app)))
-                            .closable();
-                    });
+                let profiles_id = self
+                    .nav
+                    .insert()
+                    .icon(widget::icon::from_name("system-users-symbolic"))
+                    .text(fl!("profiles"))
+                    .data::<Page>(Page::Profiles(ProfileManager::new()))
+                    .id();
+
+                let (launchers, invalid_records) = webapps::launcher::load_database();
+
+                // only bother the user when something new broke
+                if !invalid_records.is_empty() && invalid_records != self.invalid_records {
+                    self.dialogs = Some(Dialogs::InvalidRecords(invalid_records.clone()));
+                }
+                self.invalid_records = invalid_records;
+
+                launchers.into_iter().for_each(|app| {
+                    self.nav
+                        .insert()
+                        .icon(navbar_item_icon(
+                            &app.icon.path.as_path().to_str().expect("path conversion"),
+                        ))
+                        .text(app.name.clone())
+                        .data::<Page>(Page::Editor(editor::AppEditor::from(app)))
+                        .closable();
+                });
 
-                self.page = Page::Editor(AppEditor::default());
+                if on_profiles {
+                    self.nav.activate(profiles_id);
+                    self.page = Page::Profiles(ProfileManager::new());
+                } else {
+                    self.page = Page::Editor(AppEditor::default());
+                }
             }
             Message::ResetSettings => {
                 if let Some(handler) = AppConfig::config_handler() {
@@ -482,24 +613,16 @@ impl Application for QuickWebApps {
                 return cosmic::command::set_theme(cosmic::Theme::light());
             }
             Message::SaveLauncher(launcher) => {
-                if let Some(location) =
-                    webapps::database_path(&format!("{}.ron", launcher.browser.app_id.as_ref()))
-                {
-                    let content = to_string_pretty(&launcher, ron::ser::PrettyConfig::default());
-
-                    if let Ok(content) = content {
-                        let file = std::fs::File::create(location);
-
-                        if let Ok(mut f) = file {
-                            let _ = f.write_all(content.as_bytes());
-                        }
-                    }
-
-                    return task::message(Message::ReloadNavbarItems);
+                if let Err(e) = launcher.save() {
+                    tracing::error!("failed to save {}: {e}", launcher.browser.app_id.as_ref());
                 }
+
+                return task::message(Message::ReloadNavbarItems);
             }
             Message::SetIcon(icon) => {
-                let Page::Editor(app_editor) = &mut self.page;
+                let Page::Editor(app_editor) = &mut self.page else {
+                    return Task::none();
+                };
 
                 if let Some(ico) = icon {
                     println!("setting icon: {:?}", ico);
@@ -569,13 +692,22 @@ impl Application for QuickWebApps {
                     &self.key_binds,
                     MENU_ID.clone(),
                     Message::Surface,
-                    vec![(
-                        fl!("help"),
-                        vec![
-                            menu::Item::Button(fl!("settings"), None, MenuAction::Settings),
-                            menu::Item::Button(fl!("about"), None, MenuAction::About),
-                        ],
-                    )],
+                    vec![
+                        (
+                            fl!("file"),
+                            vec![
+                                menu::Item::Button(fl!("import-webapps"), None, MenuAction::Import),
+                                menu::Item::Button(fl!("export-webapps"), None, MenuAction::Export),
+                            ],
+                        ),
+                        (
+                            fl!("help"),
+                            vec![
+                                menu::Item::Button(fl!("settings"), None, MenuAction::Settings),
+                                menu::Item::Button(fl!("about"), None, MenuAction::About),
+                            ],
+                        ),
+                    ],
                 ),
         ]
     }
@@ -613,6 +745,9 @@ impl Application for QuickWebApps {
         if let Some(page) = self.nav.data::<Page>(id) {
             self.page = page.clone()
         }
+        if let Page::Profiles(manager) = &mut self.page {
+            manager.refresh();
+        }
         Task::none()
     }
 
@@ -643,9 +778,12 @@ impl Application for QuickWebApps {
     }
 
     fn view(&self) -> Element<'_, Message> {
-        let Page::Editor(content) = &self.page;
+        let content = match &self.page {
+            Page::Editor(editor) => editor.view().map(Message::Editor),
+            Page::Profiles(manager) => manager.view().map(Message::Profiles),
+        };
 
-        widget::container(content.view().map(Message::Editor))
+        widget::container(content)
             .width(Length::Fill)
             .height(Length::Fill)
             .align_x(Horizontal::Center)
@@ -684,6 +822,50 @@ impl Application for QuickWebApps {
                     .secondary_action(
                         widget::button::suggested(fl!("close")).on_press(Message::CloseDialog),
                     ),
+                Dialogs::InvalidRecords(records) => widget::dialog()
+                    .title(fl!("invalid-records"))
+                    .body(
+                        records
+                            .iter()
+                            .map(|record| format!("{}: {}", record.path.display(), record.error))
+                            .collect::<Vec<String>>()
+                            .join("\n"),
+                    )
+                    .primary_action(
+                        widget::button::standard(fl!("close")).on_press(Message::CloseDialog),
+                    ),
+                Dialogs::ImportScripts(bundle) => {
+                    let mut stripped = bundle.clone();
+                    stripped.strip_custom_code();
+
+                    let mut scripts = widget::column().spacing(8);
+                    for app in bundle.apps_with_custom_code() {
+                        scripts = scripts.push(widget::text::heading(app.name.clone()));
+
+                        let code = [&app.browser.user_css, &app.browser.user_js];
+                        for code in code.into_iter().flatten() {
+                            if !code.trim().is_empty() {
+                                scripts = scripts.push(widget::text::monotext(code.clone()));
+                            }
+                        }
+                    }
+
+                    widget::dialog()
+                        .title(fl!("import-scripts"))
+                        .body(fl!("import-scripts-body"))
+                        .control(widget::container(widget::scrollable(scripts)).max_height(320))
+                        .primary_action(
+                            widget::button::destructive(fl!("import-with-scripts"))
+                                .on_press(Message::InstallBundle(bundle.clone())),
+                        )
+                        .secondary_action(
+                            widget::button::suggested(fl!("import-without-scripts"))
+                                .on_press(Message::InstallBundle(stripped)),
+                        )
+                        .tertiary_action(
+                            widget::button::text(fl!("cancel")).on_press(Message::CloseDialog),
+                        )
+                }
             };
 
             return Some(element.into());
@@ -756,6 +938,12 @@ impl QuickWebApps {
     }
 }
 
+fn file_uri_path(uri: &str) -> String {
+    let path = uri.strip_prefix("file://").unwrap_or(uri);
+
+    urlencoding::decode(path).unwrap_or_default().to_string()
+}
+
 fn navbar_item_icon(icon: &str) -> widget::icon::Icon {
     if icon.starts_with("/") {
         let path = std::path::PathBuf::from_str(icon).expect("incorrect icon path");
@@ -776,6 +964,8 @@ pub enum ContextPage {
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum MenuAction {
     About,
+    Export,
+    Import,
     Settings,
 }
 
@@ -785,6 +975,8 @@ impl menu::action::MenuAction for MenuAction {
     fn message(&self) -> Self::Message {
         match self {
             MenuAction::About => Message::ToggleContextPage(ContextPage::About),
+            MenuAction::Export => Message::ExportBundleFilePicker,
+            MenuAction::Import => Message::ImportBundleFilePicker,
             MenuAction::Settings => Message::ToggleContextPage(ContextPage::Settings),
         }
     }
@@ -0,0 +1,185 @@
+use cosmic::{
+    Element, Task,
+    action::Action,
+    iced::{Alignment, Length},
+    task,
+    widget::{self},
+};
+use webapps::{
+    fl,
+    profiles::{Profile, format_size},
+};
+
+use crate::pages;
+
+#[derive(Debug, Clone)]
+pub struct ProfileEntry {
+    pub profile: Profile,
+    pub size: u64,
+    pub users: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProfileManager {
+    pub profiles: Vec<ProfileEntry>,
+    pub profile_name: String,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    ClearCache(usize),
+    ClearCookies(usize),
+    Create,
+    Delete(usize),
+    Duplicate(usize),
+    ProfileName(String),
+    Rename(usize),
+}
+
+impl ProfileManager {
+    pub fn new() -> Self {
+        let mut manager = Self::default();
+        manager.refresh();
+        manager
+    }
+
+    pub fn refresh(&mut self) {
+        self.profiles = Profile::list()
+            .into_iter()
+            .chain(Profile::list_isolated())
+            .map(|profile| ProfileEntry {
+                size: profile.disk_usage(),
+                users: profile.users().into_iter().map(|app| app.name).collect(),
+                profile,
+            })
+            .collect();
+    }
+
+    pub fn update(&mut self, message: Message) -> Task<Action<pages::Message>> {
+        // web app records point to the new location after renaming a used profile
+        let reload_navbar =
+            matches!(message, Message::Rename(idx) if !self.profiles[idx].users.is_empty());
+
+        let result = match message {
+            Message::ClearCache(idx) => self.profiles[idx].profile.clear_cache(),
+            Message::ClearCookies(idx) => self.profiles[idx].profile.clear_cookies(),
+            Message::Create => Profile::create(&self.profile_name).map(|_| ()),
+            Message::Delete(idx) => self.profiles[idx].profile.delete(),
+            Message::Duplicate(idx) => self.profiles[idx]
+                .profile
+                .duplicate(&self.profile_name)
+                .map(|_| ()),
+            Message::ProfileName(name) => {
+                self.profile_name = name;
+                return Task::none();
+            }
+            Message::Rename(idx) => self.profiles[idx]
+                .profile
+                .rename(&self.profile_name)
+                .map(|_| ()),
+        };
+
+        self.refresh();
+
+        match result {
+            Ok(()) => {
+                self.profile_name.clear();
+                self.error = None;
+
+                if reload_navbar {
+                    return task::message(pages::Message::ReloadNavbarItems);
+                }
+            }
+            Err(e) => self.error = Some(e.to_string()),
+        }
+
+        Task::none()
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let has_name = !self.profile_name.trim().is_empty();
+
+        let mut content = widget::column().spacing(24).push(
+            widget::row()
+                .spacing(8)
+                .align_y(Alignment::Center)
+                .push(
+                    widget::text_input(fl!("profile-name"), &self.profile_name)
+                        .on_input(Message::ProfileName),
+                )
+                .push(
+                    widget::button::suggested(fl!("profile-create"))
+                        .on_press_maybe(has_name.then_some(Message::Create)),
+                ),
+        );
+
+        if let Some(error) = &self.error {
+            content = content.push(widget::text::body(error.clone()));
+        }
+
+        if self.profiles.is_empty() {
+            content = content.push(widget::text::body(fl!("no-profiles")));
+        }
+
+        for (idx, entry) in self.profiles.iter().enumerate() {
+            let users = if entry.users.is_empty() {
+                fl!("not-used")
+            } else {
+                entry.users.join(", ")
+            };
+
+            // isolated profiles are only known by the app id, show the app's name instead
+            let title = match (entry.profile.isolated, entry.users.first()) {
+                (true, Some(app)) => fl!("isolated-profile-of", app = app.clone()),
+                (true, None) => fl!("isolated-profile-of", app = entry.profile.name.clone()),
+                (false, _) => entry.profile.name.clone(),
+            };
+
+            content = content.push(
+                widget::settings::section()
+                    .title(title)
+                    .add(widget::settings::item(
+                        fl!("disk-usage"),
+                        widget::text::body(format_size(entry.size)),
+                    ))
+                    .add(widget::settings::item(
+                        fl!("used-by"),
+                        widget::text::body(users),
+                    ))
+                    .add(
+                        widget::row()
+                            .spacing(8)
+                            .push(
+                                widget::button::standard(fl!("profile-rename")).on_press_maybe(
+                                    (has_name && !entry.profile.isolated)
+                                        .then_some(Message::Rename(idx)),
+                                ),
+                            )
+                            .push(
+                                widget::button::standard(fl!("profile-duplicate"))
+                                    .on_press_maybe(has_name.then_some(Message::Duplicate(idx))),
+                            )
+                            .push(
+                                widget::button::standard(fl!("clear-cache"))
+                                    .on_press(Message::ClearCache(idx)),
+                            )
+                            .push(
+                                widget::button::standard(fl!("clear-cookies"))
+                                    .on_press(Message::ClearCookies(idx)),
+                            )
+                            .push(widget::horizontal_space())
+                            .push(widget::button::destructive(fl!("delete")).on_press_maybe(
+                                entry.users.is_empty().then_some(Message::Delete(idx)),
+                            )),
+                    ),
+            );
+        }
+
+        widget::container(widget::scrollable(content))
+            .padding(cosmic::iced::Padding::new(0.).left(30.0).right(30.0))
+            .max_width(1000)
+            .width(Length::Fill)
+            .into()
+    }
+}
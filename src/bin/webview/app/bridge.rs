@@ -0,0 +1,193 @@
+//! Scripts injected into pages talk back to the webview through a native function
+//! the renderer gives main frames, see `renderer.rs`. Messages arrive as process
+//! messages, which tell the frame they come from.
+
+use cef::*;
+use serde::Deserialize;
+use webapps::{BRIDGE_FUNCTION, BRIDGE_MESSAGE};
+
+use super::{
+    config_for, find,
+    notifications::{self, Notification},
+    permissions,
+    simple_handler::SimpleHandler,
+};
+
+const NOTIFICATION_SCRIPT: &str = include_str!("scripts/notification.js");
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum BridgeMessage {
+    Notification {
+        id: u32,
+        title: String,
+        body: String,
+    },
+    CloseNotification {
+        id: u32,
+    },
+    Find {
+        text: String,
+        forward: bool,
+        next: bool,
+    },
+    ClearFind,
+    CloseFind,
+}
+
+fn web_notification_tag(id: u32) -> String {
+    format!("web-{}", id)
+}
+
+fn notifications_allowed(browser: Option<&Browser>) -> bool {
+    config_for(browser).is_some_and(|app| app.notifications.unwrap_or_default())
+}
+
+/// Whether `frame` shows a page of its browser's app, the only ones the bridge listens to.
+pub fn is_app_frame(frame: &Frame) -> bool {
+    let url = CefString::from(&frame.url()).to_string();
+
+    frame.is_main() == 1
+        && config_for(frame.browser().as_ref()).is_some_and(|app| app.owns_url(&url))
+}
+
+/// Injects the bridge scripts into the main frame of a page which starts loading.
+pub fn inject(frame: &mut Frame) {
+    if frame.is_main() != 1 {
+        return;
+    }
+
+    if let Some(browser) = frame.browser() {
+        permissions::dismiss_pending(&browser);
+        find::forget(&browser);
+    }
+
+    if !is_app_frame(frame) {
+        return;
+    }
+
+    let permission = if notifications_allowed(frame.browser().as_ref()) {
+        "granted"
+    } else {
+        "denied"
+    };
+
+    let script = NOTIFICATION_SCRIPT
+        .replace("%SEND%", BRIDGE_FUNCTION)
+        .replace("%PERMISSION%", permission);
+
+    let url = CefString::from(&frame.url());
+    frame.execute_java_script(Some(&CefString::from(script.as_str())), Some(&url), 0);
+}
+
+/// Handles process messages of the bridge scripts. Returns `true` when the
+/// message was one of them.
+pub fn handle_message(
+    browser: Option<&mut Browser>,
+    frame: Option<&mut Frame>,
+    message: Option<&mut ProcessMessage>,
+) -> bool {
+    let Some(message) =
+        message.filter(|message| CefString::from(&message.name()).to_string() == BRIDGE_MESSAGE)
+    else {
+        return false;
+    };
+
+    // embedded pages and pages outside the app can't use the bridge
+    if !frame.is_some_and(|frame| is_app_frame(frame)) {
+        return true;
+    }
+
+    let payload = message
+        .argument_list()
+        .map(|arguments| CefString::from(&arguments.string(0)).to_string())
+        .unwrap_or_default();
+
+    match serde_json::from_str::<BridgeMessage>(&payload) {
+        Ok(BridgeMessage::Notification { id, title, body }) => {
+            if !notifications_allowed(browser.as_deref()) {
+                return true;
+            }
+
+            notifications::send(Notification {
+                tag: Some(web_notification_tag(id)),
+                summary: title,
+                body,
+                progress: None,
+                activatable: true,
+            });
+        }
+        Ok(BridgeMessage::CloseNotification { id }) => {
+            notifications::close(&web_notification_tag(id));
+        }
+        Ok(BridgeMessage::Find {
+            text,
+            forward,
+            next,
+        }) => {
+            if let Some(browser) = browser {
+                find::find(browser, &text, forward, next);
+            }
+        }
+        Ok(BridgeMessage::ClearFind) => {
+            if let Some(browser) = browser {
+                find::clear(browser);
+            }
+        }
+        Ok(BridgeMessage::CloseFind) => {
+            if let Some(browser) = browser {
+                find::closed(browser);
+            }
+        }
+        Err(e) => eprintln!("Malformed bridge message {}: {}", payload, e),
+    }
+
+    true
+}
+
+/// Called for clicks on and dismissals of desktop notifications.
+pub fn notification_event(tag: &str, event: &str) {
+    if event == "click" {
+        if let Some(handler) = SimpleHandler::instance() {
+            let mut handler = handler.lock().expect("Failed to lock handler");
+            handler.show_main_window();
+        }
+    }
+
+    let Some(id) = tag
+        .strip_prefix("web-")
+        .and_then(|id| id.parse::<u32>().ok())
+    else {
+        return;
+    };
+
+    let script = format!(
+        "window.__webappsNotificationEvent && window.__webappsNotificationEvent({}, \"{}\")",
+        id, event
+    );
+    let mut task = RunScript::new(script);
+    post_task(ThreadId::UI, Some(&mut task));
+}
+
+wrap_task! {
+    struct RunScript {
+        script: String,
+    }
+
+    impl Task {
+        fn execute(&self) {
+            debug_assert_ne!(currently_on(ThreadId::UI), 0);
+
+            let Some(handler) = SimpleHandler::instance() else {
+                return;
+            };
+
+            let browser = handler.lock().expect("Failed to lock handler").main_browser();
+            let Some(frame) = browser.and_then(|browser| browser.main_frame()) else {
+                return;
+            };
+
+            frame.execute_java_script(Some(&CefString::from(self.script.as_str())), None, 0);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use cef::*;
+use std::sync::{Mutex, Once, OnceLock};
+use webapps::content_filter::{FilterRequest, FilterSet, ResourceKind};
+
+use super::config_for;
+
+static FILTERS: OnceLock<FilterSet> = OnceLock::new();
+static LOADING: Once = Once::new();
+// requests made while the lists are parsed, answered once they are ready
+static WAITING: Mutex<Vec<(FilterRequest, Callback)>> = Mutex::new(Vec::new());
+
+/// Parses the filter lists on a worker thread once a web app of the process blocks
+/// content. Requests of filtered pages wait until they are ready.
+pub fn start_loading(app: &crate::Browser) {
+    if !app.content_blocking.unwrap_or_default() {
+        return;
+    }
+
+    LOADING.call_once(|| {
+        std::thread::spawn(|| {
+            let _ = FILTERS.set(FilterSet::load());
+
+            let waiting = std::mem::take(&mut *WAITING.lock().expect("Failed to lock requests"));
+            let Some(filters) = FILTERS.get() else {
+                return;
+            };
+
+            for (request, callback) in waiting {
+                if filters.should_block(&request) {
+                    callback.cancel();
+                } else {
+                    callback.cont();
+                }
+            }
+        });
+    });
+}
+
+/// The parsed filters, `None` once `request` is queued to be answered when they are ready.
+fn filters_or_wait(request: &FilterRequest, callback: &Callback) -> Option<&'static FilterSet> {
+    if let Some(filters) = FILTERS.get() {
+        return Some(filters);
+    }
+
+    // checked again under the lock, the loader only takes the queue after setting the filters
+    let mut waiting = WAITING.lock().expect("Failed to lock requests");
+    if let Some(filters) = FILTERS.get() {
+        return Some(filters);
+    }
+
+    waiting.push((request.clone(), callback.clone()));
+    None
+}
+
+fn resource_kind(resource_type: ResourceType) -> ResourceKind {
+    match sys::cef_resource_type_t::from(resource_type) {
+        sys::cef_resource_type_t::RT_MAIN_FRAME => ResourceKind::Document,
+        sys::cef_resource_type_t::RT_SUB_FRAME => ResourceKind::Subdocument,
+        sys::cef_resource_type_t::RT_STYLESHEET => ResourceKind::Stylesheet,
+        sys::cef_resource_type_t::RT_SCRIPT
+        | sys::cef_resource_type_t::RT_WORKER
+        | sys::cef_resource_type_t::RT_SHARED_WORKER
+        | sys::cef_resource_type_t::RT_SERVICE_WORKER => ResourceKind::Script,
+        sys::cef_resource_type_t::RT_IMAGE | sys::cef_resource_type_t::RT_FAVICON => {
+            ResourceKind::Image
+        }
+        sys::cef_resource_type_t::RT_FONT_RESOURCE => ResourceKind::Font,
+        sys::cef_resource_type_t::RT_MEDIA => ResourceKind::Media,
+        sys::cef_resource_type_t::RT_XHR => ResourceKind::XmlHttpRequest,
+        _ => ResourceKind::Other,
+    }
+}
+
+/// Handler for the requests of a page, `None` when the page isn't filtered.
+pub fn resource_request_handler(
+    browser: Option<&Browser>,
+    frame: Option<&mut Frame>,
+    request_initiator: Option<&CefString>,
+) -> Option<ResourceRequestHandler> {
+    let browser_config = config_for(browser)?;
+
+    let page_url = request_initiator
+        .map(CefString::to_string)
+        .filter(|initiator| !initiator.is_empty() && initiator != "null")
+        .or_else(|| frame.map(|frame| CefString::from(&frame.url()).to_string()))
+        .unwrap_or_default();
+
+    // lists still loading can't be known to be empty yet
+    if !browser_config.filters_page(&page_url) || FILTERS.get().is_some_and(FilterSet::is_empty) {
+        return None;
+    }
+
+    Some(ContentFilterHandler::new(page_url))
+}
+
+wrap_resource_request_handler! {
+    struct ContentFilterHandler {
+        page_url: String,
+    }
+
+    impl ResourceRequestHandler {
+        fn on_before_resource_load(
+            &self,
+            _browser: Option<&mut Browser>,
+            _frame: Option<&mut Frame>,
+            request: Option<&mut Request>,
+            callback: Option<&mut Callback>,
+        ) -> ReturnValue {
+            let (Some(request), Some(callback)) = (request, callback) else {
+                return ReturnValue::CONTINUE;
+            };
+
+            let url = CefString::from(&request.url()).to_string();
+            let kind = resource_kind(request.resource_type());
+
+            // the app's own pages always load, only what they pull in is filtered
+            if kind == ResourceKind::Document {
+                return ReturnValue::CONTINUE;
+            }
+
+            let Some(filter_request) = FilterRequest::new(&url, Some(&self.page_url), kind) else {
+                return ReturnValue::CONTINUE;
+            };
+
+            let Some(filters) = filters_or_wait(&filter_request, callback) else {
+                return ReturnValue::CONTINUE_ASYNC;
+            };
+
+            if filters.should_block(&filter_request) {
+                ReturnValue::CANCEL
+            } else {
+                ReturnValue::CONTINUE
+            }
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use ashpd::desktop::file_chooser::SelectedFiles;
+use cef::*;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+use webapps::fl;
+
+use super::{
+    config_for,
+    notifications::{self, Notification},
+};
+
+// notify again only after the progress moved this many percent
+const PROGRESS_STEP: i32 = 5;
+
+// download handler callbacks all run on the UI thread
+thread_local! {
+    static PENDING: RefCell<HashMap<u32, BeforeDownloadCallback>> = RefCell::new(HashMap::new());
+    // downloads still waiting for their target, which can only be cancelled through these
+    static WAITING: RefCell<HashMap<u32, DownloadItemCallback>> = RefCell::new(HashMap::new());
+    static CANCELLED: RefCell<HashSet<u32>> = RefCell::new(HashSet::new());
+    static PROGRESS: RefCell<HashMap<u32, i32>> = RefCell::new(HashMap::new());
+}
+
+fn download_location(browser: Option<&Browser>) -> PathBuf {
+    match config_for(browser) {
+        Some(browser_config) => browser_config.download_location(),
+        None => dirs::download_dir().unwrap_or_default(),
+    }
+}
+
+/// Picks a free file name, `file (1).zip` when `file.zip` exists already.
+fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+
+    if !path.exists() {
+        return path;
+    }
+
+    let name = Path::new(name);
+    let stem = name
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = name
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+
+    (1..)
+        .map(|idx| dir.join(format!("{stem} ({idx}){extension}")))
+        .find(|path| !path.exists())
+        .unwrap_or(path)
+}
+
+async fn ask_save_path(name: &str, folder: &Path) -> ashpd::Result<Option<PathBuf>> {
+    let response = SelectedFiles::save_file()
+        .title(fl!("save-download").as_str())
+        .accept_label(fl!("save").as_str())
+        .modal(true)
+        .current_name(name)
+        .current_folder(folder)?
+        .send()
+        .await?
+        .response();
+
+    // an error response means the dialog was dismissed
+    let Ok(files) = response else {
+        return Ok(None);
+    };
+
+    Ok(files
+        .uris()
+        .first()
+        .and_then(|uri| url::Url::parse(uri.as_str()).ok())
+        .and_then(|uri| uri.to_file_path().ok()))
+}
+
+/// Asks for the target through the file chooser portal, `None` when the user cancelled.
+fn choose_path(name: &str, folder: &Path) -> Option<PathBuf> {
+    let result = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(anyhow::Error::from)
+        .and_then(|runtime| {
+            runtime
+                .block_on(ask_save_path(name, folder))
+                .map_err(anyhow::Error::from)
+        });
+
+    match result {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!(
+                "Save dialog unavailable, saving to {}: {}",
+                folder.display(),
+                e
+            );
+            Some(unique_path(folder, name))
+        }
+    }
+}
+
+fn notify(id: u32, summary: String, body: &str, progress: Option<i32>) {
+    notifications::send(Notification {
+        tag: Some(format!("download-{}", id)),
+        summary,
+        body: body.to_string(),
+        progress,
+        activatable: false,
+    });
+}
+
+wrap_download_handler! {
+    pub struct WebviewDownloadHandler {}
+
+    impl DownloadHandler {
+        fn can_download(
+            &self,
+            _browser: Option<&mut Browser>,
+            _url: Option<&CefString>,
+            _request_method: Option<&CefString>,
+        ) -> i32 {
+            1
+        }
+
+        fn on_before_download(
+            &self,
+            browser: Option<&mut Browser>,
+            download_item: Option<&mut DownloadItem>,
+            suggested_name: Option<&CefString>,
+            callback: Option<&mut BeforeDownloadCallback>,
+        ) -> i32 {
+            let (Some(download_item), Some(callback)) = (download_item, callback) else {
+                return 0;
+            };
+
+            let id = download_item.id();
+            let name = suggested_name
+                .map(CefString::to_string)
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| String::from("download"));
+
+            PENDING.with_borrow_mut(|pending| pending.insert(id, callback.clone()));
+
+            // the portal dialog must not block the UI thread
+            let folder = download_location(browser.as_deref());
+            std::thread::spawn(move || {
+                let path = choose_path(&name, &folder);
+                let mut task = ContinueDownload::new(id, path);
+                post_task(ThreadId::UI, Some(&mut task));
+            });
+
+            1
+        }
+
+        fn on_download_updated(
+            &self,
+            _browser: Option<&mut Browser>,
+            download_item: Option<&mut DownloadItem>,
+            callback: Option<&mut DownloadItemCallback>,
+        ) {
+            let Some(download_item) = download_item else {
+                return;
+            };
+
+            let id = download_item.id();
+
+            if CANCELLED.with_borrow(|cancelled| cancelled.contains(&id)) {
+                if download_item.is_canceled() == 1 {
+                    CANCELLED.with_borrow_mut(|cancelled| cancelled.remove(&id));
+                    PENDING.with_borrow_mut(|pending| pending.remove(&id));
+                } else if let Some(callback) = callback {
+                    callback.cancel();
+                }
+                return;
+            }
+
+            if PENDING.with_borrow(|pending| pending.contains_key(&id)) {
+                if let Some(callback) = callback {
+                    WAITING.with_borrow_mut(|waiting| waiting.insert(id, callback.clone()));
+                }
+                return;
+            }
+
+            let path = PathBuf::from(CefString::from(&download_item.full_path()).to_string());
+            let Some(file_name) = path.file_name().map(|name| name.to_string_lossy().to_string())
+            else {
+                // target not chosen yet
+                return;
+            };
+
+            if download_item.is_complete() == 1 {
+                PROGRESS.with_borrow_mut(|progress| progress.remove(&id));
+                notify(id, fl!("download-finished"), &file_name, None);
+            } else if download_item.is_canceled() == 1 || download_item.is_interrupted() == 1 {
+                PROGRESS.with_borrow_mut(|progress| progress.remove(&id));
+                notify(id, fl!("download-failed"), &file_name, None);
+            } else if download_item.is_in_progress() == 1 {
+                let percent = download_item.percent_complete();
+                let last = PROGRESS.with_borrow(|progress| progress.get(&id).copied());
+
+                if last.is_none_or(|last| percent >= last + PROGRESS_STEP) {
+                    PROGRESS.with_borrow_mut(|progress| progress.insert(id, percent));
+                    notify(
+                        id,
+                        fl!("downloading"),
+                        &file_name,
+                        (percent >= 0).then_some(percent),
+                    );
+                }
+            }
+        }
+    }
+}
+
+wrap_task! {
+    struct ContinueDownload {
+        id: u32,
+        path: Option<PathBuf>,
+    }
+
+    impl Task {
+        fn execute(&self) {
+            debug_assert_ne!(currently_on(ThreadId::UI), 0);
+
+            let waiting = WAITING.with_borrow_mut(|waiting| waiting.remove(&self.id));
+
+            match &self.path {
+                Some(path) => {
+                    let Some(callback) = PENDING.with_borrow_mut(|pending| pending.remove(&self.id))
+                    else {
+                        return;
+                    };
+
+                    let path = CefString::from(path.display().to_string().as_str());
+                    callback.cont(Some(&path), 0);
+                }
+                None => {
+                    // the callback stays pending, continuing would start writing to the default
+                    // location, the download item cancels it instead
+                    CANCELLED.with_borrow_mut(|cancelled| cancelled.insert(self.id));
+
+                    if let Some(waiting) = waiting {
+                        waiting.cancel();
+                    }
+                }
+            }
+        }
+    }
+}
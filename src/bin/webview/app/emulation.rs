@@ -0,0 +1,58 @@
+//! Mobile emulation through the DevTools protocol, so pages get a mobile
+//! viewport and touch events along with the mobile user agent.
+
+use cef::*;
+use webapps::device::Device;
+
+/// Touch points reported to the page, enough for pinch gestures.
+const MAX_TOUCH_POINTS: i32 = 5;
+
+fn execute(host: &BrowserHost, method: &str, params: &mut DictionaryValue) {
+    let result = host.execute_dev_tools_method(0, Some(&CefString::from(method)), Some(params));
+
+    if result == 0 {
+        eprintln!("Failed to run DevTools method {}", method);
+    }
+}
+
+fn key(name: &str) -> CefString {
+    CefString::from(name)
+}
+
+/// Makes the page in `host` look like it runs on `device`.
+pub fn emulate(host: &BrowserHost, device: &Device) {
+    let Some(mut metrics) = dictionary_value_create() else {
+        return;
+    };
+    metrics.set_int(Some(&key("width")), device.width as i32);
+    metrics.set_int(Some(&key("height")), device.height as i32);
+    metrics.set_int(Some(&key("screenWidth")), device.width as i32);
+    metrics.set_int(Some(&key("screenHeight")), device.height as i32);
+    metrics.set_double(Some(&key("deviceScaleFactor")), device.scale_factor);
+    metrics.set_bool(Some(&key("mobile")), 1);
+    execute(host, "Emulation.setDeviceMetricsOverride", &mut metrics);
+
+    let Some(mut touch) = dictionary_value_create() else {
+        return;
+    };
+    touch.set_bool(Some(&key("enabled")), 1);
+    touch.set_int(Some(&key("maxTouchPoints")), MAX_TOUCH_POINTS);
+    execute(host, "Emulation.setTouchEmulationEnabled", &mut touch);
+
+    // a mouse has to stand in for the finger
+    let Some(mut mouse) = dictionary_value_create() else {
+        return;
+    };
+    mouse.set_bool(Some(&key("enabled")), 1);
+    mouse.set_string(Some(&key("configuration")), Some(&key("mobile")));
+    execute(host, "Emulation.setEmitTouchEventsForMouse", &mut mouse);
+}
+
+/// Gives the page in `host` a user agent other than the one of the process.
+pub fn override_user_agent(host: &BrowserHost, user_agent: &str) {
+    let Some(mut params) = dictionary_value_create() else {
+        return;
+    };
+    params.set_string(Some(&key("userAgent")), Some(&key(user_agent)));
+    execute(host, "Emulation.setUserAgentOverride", &mut params);
+}
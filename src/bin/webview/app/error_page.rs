@@ -0,0 +1,92 @@
+//! Page shown in place of one which failed to load. It retries on its own once
+//! the network comes back.
+
+use base64::Engine as _;
+use cef::*;
+use clap::Parser as _;
+use webapps::{WebviewArgs, fl, launcher::WebAppLauncher};
+
+const TEMPLATE: &str = include_str!("error_page.html");
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Errors caused by a missing or changing network connection. A host which doesn't
+/// resolve is as likely a typo or a site gone away, so it gets the regular page.
+fn is_offline(error_code: sys::cef_errorcode_t) -> bool {
+    matches!(
+        error_code,
+        sys::cef_errorcode_t::ERR_INTERNET_DISCONNECTED | sys::cef_errorcode_t::ERR_NETWORK_CHANGED
+    )
+}
+
+/// The web app's icon as an `<img>`, empty when there's none.
+fn icon_element(launcher: Option<&WebAppLauncher>) -> String {
+    let Some(path) = launcher.map(|launcher| &launcher.icon.path) else {
+        return String::new();
+    };
+
+    let Ok(data) = std::fs::read(path) else {
+        return String::new();
+    };
+
+    let mime_type = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("svg") => "image/svg+xml",
+        _ => "image/png",
+    };
+
+    format!(
+        r#"<img alt="" src="data:{};base64,{}" />"#,
+        mime_type,
+        base64::engine::general_purpose::STANDARD.encode(data)
+    )
+}
+
+/// Renders the error page for `failed_url`.
+pub fn render(
+    app: Option<&crate::Browser>,
+    failed_url: &str,
+    error_code: sys::cef_errorcode_t,
+    error_text: &str,
+) -> String {
+    let app_id = app
+        .map(|app| app.app_id.to_string())
+        .unwrap_or_else(|| WebviewArgs::parse().id);
+    let launcher = WebAppLauncher::from_appid(&app_id);
+    let app_name = launcher
+        .as_ref()
+        .map(|launcher| launcher.name.clone())
+        .unwrap_or(app_id);
+
+    let (title, message) = if is_offline(error_code) {
+        (fl!("error-offline"), fl!("error-offline-message"))
+    } else {
+        (
+            fl!("error-unreachable", app = app_name.as_str()),
+            fl!("error-unreachable-message"),
+        )
+    };
+
+    // inside the page's script, so `</script>` in the URL can't end it
+    let failed_url_json = serde_json::json!(failed_url)
+        .to_string()
+        .replace("</", "<\\/");
+
+    TEMPLATE
+        .replace("%FAILED_URL%", &failed_url_json)
+        .replace("%ICON%", &icon_element(launcher.as_ref()))
+        .replace("%TITLE%", &escape_html(&title))
+        .replace("%MESSAGE%", &escape_html(&message))
+        .replace("%RETRY%", &escape_html(&fl!("retry")))
+        .replace("%DETAILS%", &escape_html(&fl!("error-details")))
+        .replace("%URL%", &escape_html(failed_url))
+        .replace(
+            "%ERROR%",
+            &escape_html(&format!("{} ({})", error_text, error_code as i32)),
+        )
+}
@@ -0,0 +1,122 @@
+//! Find in page, with a find bar injected into the page.
+
+use cef::*;
+use std::{cell::RefCell, collections::HashSet};
+use webapps::{BRIDGE_FUNCTION, fl};
+
+use super::bridge;
+
+const FIND_BAR_SCRIPT: &str = include_str!("scripts/find.js");
+
+// browsers with an open find bar, everything here runs on the UI thread
+thread_local! {
+    static OPEN: RefCell<HashSet<i32>> = RefCell::new(HashSet::new());
+}
+
+fn run_script(browser: &Browser, script: &str) {
+    if let Some(frame) = browser.main_frame() {
+        frame.execute_java_script(Some(&CefString::from(script)), None, 0);
+    }
+}
+
+/// Opens the find bar, or focuses it when it's open already. Pages outside the
+/// app can't talk to the bridge, they get no find bar.
+pub fn show_bar(browser: &Browser) {
+    if !browser
+        .main_frame()
+        .is_some_and(|frame| bridge::is_app_frame(&frame))
+    {
+        return;
+    }
+
+    let labels = serde_json::json!({
+        "find": fl!("find-in-page"),
+        "previous": fl!("find-previous"),
+        "next": fl!("find-next"),
+        "close": fl!("close"),
+        "none": fl!("find-no-matches"),
+    });
+    let script = format!(
+        "({})({}, {})",
+        FIND_BAR_SCRIPT.trim().trim_end_matches(';'),
+        serde_json::json!(BRIDGE_FUNCTION),
+        labels
+    );
+
+    run_script(browser, &script);
+    OPEN.with_borrow_mut(|open| open.insert(browser.identifier()));
+}
+
+/// Closes the find bar. Returns `false` when there was none, so the key goes to the page.
+pub fn stop(browser: &Browser) -> bool {
+    if !OPEN.with_borrow(|open| open.contains(&browser.identifier())) {
+        return false;
+    }
+
+    run_script(
+        browser,
+        "window.__webappsFindBar && window.__webappsFindBar.close()",
+    );
+
+    true
+}
+
+/// Searches the page for `text`, `find_next` continues the previous search.
+pub fn find(browser: &Browser, text: &str, forward: bool, find_next: bool) {
+    if let Some(host) = browser.host() {
+        host.find(
+            Some(&CefString::from(text)),
+            forward.into(),
+            0,
+            find_next.into(),
+        );
+    }
+}
+
+/// Clears the highlighted matches.
+pub fn clear(browser: &Browser) {
+    if let Some(host) = browser.host() {
+        host.stop_finding(1);
+    }
+}
+
+/// Ends the search once the find bar is gone.
+pub fn closed(browser: &Browser) {
+    clear(browser);
+    forget(browser);
+}
+
+/// The find bar went away with the page it was shown in.
+pub fn forget(browser: &Browser) {
+    OPEN.with_borrow_mut(|open| open.remove(&browser.identifier()));
+}
+
+wrap_find_handler! {
+    pub struct FindBarHandler {}
+
+    impl FindHandler {
+        fn on_find_result(
+            &self,
+            browser: Option<&mut Browser>,
+            _identifier: i32,
+            count: i32,
+            _selection_rect: Option<&Rect>,
+            active_match_ordinal: i32,
+            final_update: i32,
+        ) {
+            let Some(browser) = browser else {
+                return;
+            };
+
+            if final_update != 1 {
+                return;
+            }
+
+            let script = format!(
+                "window.__webappsFindResult && window.__webappsFindResult({}, {})",
+                active_match_ordinal, count
+            );
+            run_script(browser, &script);
+        }
+    }
+}
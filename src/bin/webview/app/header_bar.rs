@@ -0,0 +1,117 @@
+//! Minimal header bar with navigation buttons, shown above the page for apps
+//! which ask for it.
+
+use cef::*;
+use webapps::fl;
+
+use super::shortcuts::{self, Action};
+
+const HEIGHT: i32 = 36;
+
+wrap_panel_delegate! {
+    struct HeaderPanelDelegate {}
+
+    impl ViewDelegate {
+        fn preferred_size(&self, _view: Option<&mut View>) -> Size {
+            Size {
+                width: 0,
+                height: HEIGHT,
+            }
+        }
+    }
+
+    impl PanelDelegate {}
+}
+
+wrap_button_delegate! {
+    struct HeaderButtonDelegate {
+        browser_view: BrowserView,
+    }
+
+    impl ViewDelegate {}
+
+    impl ButtonDelegate {
+        fn on_button_pressed(&self, button: Option<&mut Button>) {
+            let Some(action) = button.and_then(|button| Action::from_id(button.id())) else {
+                return;
+            };
+
+            if let Some(mut browser) = self.browser_view.browser() {
+                shortcuts::run(&mut browser, action);
+            }
+        }
+    }
+}
+
+/// `#rrggbb` as an opaque ARGB color.
+fn parse_color(color: &str) -> Option<u32> {
+    let hex = color.trim().strip_prefix('#')?;
+
+    if hex.len() != 6 {
+        return None;
+    }
+
+    u32::from_str_radix(hex, 16)
+        .ok()
+        .map(|rgb| 0xFF00_0000 | rgb)
+}
+
+/// Puts a header bar above `browser_view`, tinted with the site's theme color.
+pub fn add_to_window(window: &mut Window, browser_view: &BrowserView, theme_color: Option<&str>) {
+    let settings = BoxLayoutSettings {
+        horizontal: 0,
+        ..Default::default()
+    };
+    let Some(layout) = window.set_to_box_layout(Some(&settings)) else {
+        return;
+    };
+
+    let mut delegate = HeaderPanelDelegate::new();
+    let Some(panel) = panel_create(Some(&mut delegate)) else {
+        return;
+    };
+
+    panel.set_to_box_layout(Some(&BoxLayoutSettings {
+        horizontal: 1,
+        between_child_spacing: 4,
+        inside_border_insets: Insets {
+            top: 4,
+            left: 4,
+            bottom: 4,
+            right: 4,
+        },
+        ..Default::default()
+    }));
+
+    if let Some(color) = theme_color.and_then(parse_color) {
+        panel.set_background_color(color);
+    }
+
+    let buttons = [
+        (Action::Back, "\u{2190}", fl!("back")),
+        (Action::Forward, "\u{2192}", fl!("forward")),
+        (Action::Reload, "\u{27F3}", fl!("reload")),
+        (Action::Find, "\u{2315}", fl!("find-in-page")),
+    ];
+
+    for (action, label, tooltip) in buttons {
+        let mut delegate = HeaderButtonDelegate::new(browser_view.clone());
+        let Some(button) = label_button_create(Some(&mut delegate), Some(&CefString::from(label)))
+        else {
+            continue;
+        };
+
+        button.set_id(action as i32);
+        button.set_tooltip_text(Some(&CefString::from(tooltip.as_str())));
+
+        let mut view = View::from(&button);
+        panel.add_child_view(Some(&mut view));
+    }
+
+    let mut view = View::from(&panel);
+    window.add_child_view(Some(&mut view));
+
+    let mut view = View::from(browser_view);
+    window.add_child_view(Some(&mut view));
+    layout.set_flex_for_view(Some(&mut view), 1);
+}
@@ -0,0 +1,116 @@
+//! Keeps a single webview process per profile. The profile of a running
+//! instance is locked by CEF, so later launches of the web app, or of another
+//! one sharing the profile, hand their request over a Unix socket in the
+//! profile directory and exit.
+
+use cef::*;
+use std::{
+    io::{BufRead as _, BufReader, Write as _},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use webapps::browser::Browser;
+
+use super::simple_app::LaunchApp;
+
+/// A launch of the web app `app_id`, handed to the process holding its profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+    /// Brings the app's window to front, or opens it when the app isn't shown yet
+    Focus { app_id: String },
+    /// Opens `url` in a window of the app
+    Open { app_id: String, url: String },
+}
+
+impl Request {
+    fn encode(&self) -> String {
+        match self {
+            Request::Focus { app_id } => format!("focus {}\n", app_id),
+            Request::Open { app_id, url } => format!("open {} {}\n", app_id, url),
+        }
+    }
+
+    fn decode(line: &str) -> Option<Self> {
+        let (command, args) = line.trim_end().split_once(' ')?;
+
+        match (command, args.split_once(' ')) {
+            ("focus", None) => Some(Request::Focus {
+                app_id: args.to_string(),
+            }),
+            ("open", Some((app_id, url))) => Some(Request::Open {
+                app_id: app_id.to_string(),
+                url: url.to_string(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+pub fn socket_path(browser: &Browser) -> PathBuf {
+    browser
+        .profile
+        .join(format!("{}.sock", browser.app_id.as_ref()))
+}
+
+/// Socket of another running web app holding the shared profile of `browser`. CEF
+/// locks the profile for the process using it, so that one has to show `browser` too.
+pub fn profile_owner(browser: &Browser) -> Option<PathBuf> {
+    let own_socket = socket_path(browser);
+
+    std::fs::read_dir(&browser.profile)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path != &own_socket && path.extension().is_some_and(|ext| ext == "sock"))
+        .find(|path| UnixStream::connect(path).is_ok())
+}
+
+/// Hands the request to a running instance. Returns `false` when there is none.
+pub fn forward(path: &Path, request: &Request) -> bool {
+    let Ok(mut stream) = UnixStream::connect(path) else {
+        return false;
+    };
+
+    stream.write_all(request.encode().as_bytes()).is_ok()
+}
+
+/// Serves requests of later launches for the lifetime of the process.
+pub fn listen(path: &Path) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    // socket left behind by an instance which didn't exit cleanly
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let _ = stream.set_read_timeout(Some(Duration::from_secs(1)));
+
+            let mut line = String::new();
+            if BufReader::new(stream).read_line(&mut line).is_err() {
+                continue;
+            }
+
+            match Request::decode(&line) {
+                Some(request) => handle(request),
+                None => eprintln!("Ignoring malformed instance request: {}", line.trim_end()),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+fn handle(request: Request) {
+    let mut task = match request {
+        Request::Focus { app_id } => LaunchApp::new(app_id, None),
+        Request::Open { app_id, url } => LaunchApp::new(app_id, Some(url)),
+    };
+    post_task(ThreadId::UI, Some(&mut task));
+}
@@ -2,13 +2,100 @@
 
 use cef::*;
 use clap::Parser as _;
-use webapps::{DESKTOP_UA, MOBILE_UA, WebviewArgs};
-
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
+use webapps::WebviewArgs;
+
+pub mod bridge;
+pub mod content_filter;
+pub mod downloads;
+pub mod emulation;
+pub mod error_page;
+pub mod find;
+pub mod header_bar;
+pub mod instance;
+pub mod navigation;
+pub mod notifications;
+pub mod permissions;
+pub mod shortcuts;
 pub mod simple_app;
 pub mod simple_handler;
+pub mod tray;
+pub mod user_content;
 
 pub struct Library;
 
+static BROWSER_CONFIG: OnceLock<Option<crate::Browser>> = OnceLock::new();
+// web apps sharing the profile, started later and shown by this process
+static GUEST_CONFIGS: Mutex<Vec<&'static crate::Browser>> = Mutex::new(Vec::new());
+// browser identifiers and the web app they show, requests arrive on several threads
+static BROWSER_APPS: OnceLock<Mutex<HashMap<i32, &'static crate::Browser>>> = OnceLock::new();
+
+/// Settings of the web app this process was started for, loaded once.
+pub fn browser_config() -> Option<&'static crate::Browser> {
+    BROWSER_CONFIG
+        .get_or_init(|| crate::Browser::from_appid(&WebviewArgs::parse().id))
+        .as_ref()
+}
+
+/// Settings of `app_id`, which is either the web app this process was started for or
+/// one sharing its profile. CEF locks a profile to one process, so those are shown here.
+pub fn app_config(app_id: &str) -> Option<&'static crate::Browser> {
+    if let Some(browser_config) =
+        browser_config().filter(|browser_config| browser_config.app_id.as_ref() == app_id)
+    {
+        return Some(browser_config);
+    }
+
+    let mut guests = GUEST_CONFIGS.lock().expect("Failed to lock guest configs");
+
+    if let Some(guest) = guests.iter().find(|guest| guest.app_id.as_ref() == app_id) {
+        return Some(guest);
+    }
+
+    // apps with a profile of their own run in their own process
+    let guest = crate::Browser::from_appid(app_id)
+        .filter(|guest| browser_config().is_some_and(|owner| owner.profile == guest.profile))?;
+    let guest: &'static crate::Browser = Box::leak(Box::new(guest));
+    guests.push(guest);
+
+    Some(guest)
+}
+
+fn browser_apps() -> &'static Mutex<HashMap<i32, &'static crate::Browser>> {
+    BROWSER_APPS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Records which web app `browser` shows.
+pub fn register_browser(browser: &Browser, app: &'static crate::Browser) {
+    browser_apps()
+        .lock()
+        .expect("Failed to lock browser apps")
+        .insert(browser.identifier(), app);
+}
+
+pub fn unregister_browser(browser: &Browser) {
+    browser_apps()
+        .lock()
+        .expect("Failed to lock browser apps")
+        .remove(&browser.identifier());
+}
+
+/// Settings of the web app shown by `browser`.
+pub fn config_for(browser: Option<&Browser>) -> Option<&'static crate::Browser> {
+    browser
+        .and_then(|browser| {
+            browser_apps()
+                .lock()
+                .expect("Failed to lock browser apps")
+                .get(&browser.identifier())
+                .copied()
+        })
+        .or_else(browser_config)
+}
+
 #[allow(dead_code)]
 pub fn load_cef() -> Library {
     let library = Library;
@@ -47,7 +134,42 @@ pub fn run_main(main_args: &MainArgs, cmd_line: &CommandLine, sandbox_info: *mut
         return;
     };
 
-    let mobile_ua = browser_config.try_simulate_mobile.unwrap_or(false);
+    let socket_path = instance::socket_path(&browser_config);
+    // urls outside of the web app just bring the running window to front
+    let app_id = args.id.clone();
+    let request = match args
+        .requested_url()
+        .and_then(|url| browser_config.resolve_url(url))
+    {
+        Some(url) => instance::Request::Open { app_id, url },
+        None => instance::Request::Focus { app_id },
+    };
+
+    if instance::forward(&socket_path, &request) {
+        println!("web app {} is already running", args.id);
+        return;
+    }
+
+    // the process of another web app holds the shared profile, it opens this one's window
+    if let Some(owner) = instance::profile_owner(&browser_config) {
+        if instance::forward(&owner, &request) {
+            println!(
+                "web app {} opens in the process holding its profile",
+                args.id
+            );
+            return;
+        }
+    }
+
+    if let Err(e) = instance::listen(&socket_path) {
+        eprintln!("Failed to listen for other instances: {}", e);
+    }
+
+    content_filter::start_loading(&browser_config);
+
+    // entry 4 is CHROME_VERSION_MAJOR of the CEF library actually loaded
+    let user_agent = browser_config.user_agent(version_info(4));
+    let profile = browser_config.profile.clone();
     let root_cache_path = browser_config.profile;
 
     let path = root_cache_path.join("cache");
@@ -59,25 +181,31 @@ pub fn run_main(main_args: &MainArgs, cmd_line: &CommandLine, sandbox_info: *mut
         browser_subprocess_path: CefString::from(helper_path.as_str()),
         root_cache_path,
         cache_path,
-        user_agent: if mobile_ua {
-            CefString::from(MOBILE_UA)
-        } else {
-            CefString::from(DESKTOP_UA)
-        },
+        user_agent: CefString::from(user_agent.as_str()),
         ..Default::default()
     };
 
-    assert_eq!(
-        initialize(
-            Some(main_args),
-            Some(&settings),
-            Some(&mut app),
-            sandbox_info,
-        ),
-        1
-    );
+    if initialize(
+        Some(main_args),
+        Some(&settings),
+        Some(&mut app),
+        sandbox_info,
+    ) != 1
+    {
+        // most likely an instance of an older build holding the profile
+        eprintln!(
+            "Failed to initialize CEF with profile {}",
+            profile.display()
+        );
+        let _ = std::fs::remove_file(socket_path);
+        return;
+    }
+
+    tray::start();
 
     run_message_loop();
 
     shutdown();
+
+    let _ = std::fs::remove_file(socket_path);
 }
@@ -0,0 +1,77 @@
+use cef::*;
+
+use super::{config_for, content_filter};
+
+/// Hands `url` to the default browser when the policy of the web app shown by
+/// `browser` doesn't keep it. Returns `true` when the navigation left the app.
+pub fn open_externally(browser: Option<&Browser>, url: &str) -> bool {
+    let Some(browser_config) = config_for(browser) else {
+        return false;
+    };
+
+    if browser_config.stays_in_app(url) {
+        return false;
+    }
+
+    if let Err(e) = open::that_detached(url) {
+        eprintln!("Failed to open {} in the default browser: {}", url, e);
+    }
+
+    true
+}
+
+wrap_request_handler! {
+    pub struct NavigationRequestHandler {}
+
+    impl RequestHandler {
+        fn on_before_browse(
+            &self,
+            browser: Option<&mut Browser>,
+            frame: Option<&mut Frame>,
+            request: Option<&mut Request>,
+            _user_gesture: i32,
+            _is_redirect: i32,
+        ) -> i32 {
+            let (Some(frame), Some(request)) = (frame, request) else {
+                return 0;
+            };
+
+            // redirects come through here again with their target, so a link bouncing
+            // through the app's own origin still leaves it, sign-in pages stay through
+            // the allowed origins
+            if frame.is_main() != 1 {
+                return 0;
+            }
+
+            let url = CefString::from(&request.url()).to_string();
+
+            open_externally(browser.as_deref(), &url).into()
+        }
+
+        fn resource_request_handler(
+            &self,
+            browser: Option<&mut Browser>,
+            frame: Option<&mut Frame>,
+            _request: Option<&mut Request>,
+            _is_navigation: i32,
+            _is_download: i32,
+            request_initiator: Option<&CefString>,
+            _disable_default_handling: Option<&mut i32>,
+        ) -> Option<ResourceRequestHandler> {
+            content_filter::resource_request_handler(browser.as_deref(), frame, request_initiator)
+        }
+
+        fn on_open_urlfrom_tab(
+            &self,
+            browser: Option<&mut Browser>,
+            _frame: Option<&mut Frame>,
+            target_url: Option<&CefString>,
+            _target_disposition: WindowOpenDisposition,
+            _user_gesture: i32,
+        ) -> i32 {
+            let url = target_url.map(CefString::to_string).unwrap_or_default();
+
+            open_externally(browser.as_deref(), &url).into()
+        }
+    }
+}
@@ -0,0 +1,227 @@
+//! Desktop notifications through `org.freedesktop.Notifications`.
+
+use clap::Parser as _;
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex, OnceLock,
+        mpsc::{self, Receiver, Sender},
+    },
+};
+use webapps::{APP_ID, WebviewArgs, launcher::WebAppLauncher};
+use zbus::{
+    blocking::{Connection, Proxy},
+    zvariant::Value,
+};
+
+const DESTINATION: &str = "org.freedesktop.Notifications";
+const PATH: &str = "/org/freedesktop/Notifications";
+
+#[derive(Debug, Clone, Default)]
+pub struct Notification {
+    /// Notifications with the same tag replace each other
+    pub tag: Option<String>,
+    pub summary: String,
+    pub body: String,
+    /// Progress in percent, shown by servers supporting the `value` hint
+    pub progress: Option<i32>,
+    /// Clicking the notification focuses the web app
+    pub activatable: bool,
+}
+
+enum Command {
+    Show(Notification),
+    Close(String),
+}
+
+// server ids of shown notifications mapped to their tags
+type Shown = Arc<Mutex<HashMap<u32, String>>>;
+
+static SENDER: OnceLock<Sender<Command>> = OnceLock::new();
+
+/// Queues a notification, D-Bus calls happen on a worker thread so the UI never waits on them.
+pub fn send(notification: Notification) {
+    queue(Command::Show(notification));
+}
+
+/// Withdraws the notification shown with `tag`.
+pub fn close(tag: &str) {
+    queue(Command::Close(tag.to_string()));
+}
+
+fn queue(command: Command) {
+    let sender = SENDER.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || worker(receiver));
+        sender
+    });
+
+    let _ = sender.send(command);
+}
+
+fn worker(receiver: Receiver<Command>) {
+    let connection = match Connection::session() {
+        Ok(connection) => connection,
+        Err(e) => {
+            eprintln!("Failed to connect to the session bus: {}", e);
+            return;
+        }
+    };
+
+    let shown: Shown = Arc::default();
+
+    {
+        let connection = connection.clone();
+        let shown = shown.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = listen(&connection, &shown) {
+                eprintln!("Failed to listen for notification actions: {}", e);
+            }
+        });
+    }
+
+    let (app_name, app_icon) = app_identity();
+
+    for command in receiver {
+        let result = match command {
+            Command::Show(notification) => {
+                show(&connection, &shown, &app_name, &app_icon, notification)
+            }
+            Command::Close(tag) => close_tagged(&connection, &shown, &tag),
+        };
+
+        if let Err(e) = result {
+            eprintln!("Failed to send notification: {}", e);
+        }
+    }
+}
+
+fn show(
+    connection: &Connection,
+    shown: &Shown,
+    app_name: &str,
+    app_icon: &str,
+    notification: Notification,
+) -> zbus::Result<()> {
+    let replaces_id = notification
+        .tag
+        .as_ref()
+        .and_then(|tag| server_id(shown, tag))
+        .unwrap_or(0);
+
+    let mut hints: HashMap<&str, Value> = HashMap::new();
+    if let Some(progress) = notification.progress {
+        hints.insert("value", Value::from(progress));
+    }
+
+    let actions = if notification.activatable {
+        vec!["default", app_name]
+    } else {
+        Vec::new()
+    };
+
+    let reply = connection.call_method(
+        Some(DESTINATION),
+        PATH,
+        Some(DESTINATION),
+        "Notify",
+        &(
+            app_name,
+            replaces_id,
+            app_icon,
+            &notification.summary,
+            &notification.body,
+            actions,
+            hints,
+            -1i32,
+        ),
+    )?;
+    let id = reply.body().deserialize::<u32>()?;
+
+    if let Some(tag) = notification.tag {
+        let mut shown = shown.lock().expect("Failed to lock notifications");
+        shown.retain(|_, shown_tag| shown_tag != &tag);
+        shown.insert(id, tag);
+    }
+
+    Ok(())
+}
+
+fn close_tagged(connection: &Connection, shown: &Shown, tag: &str) -> zbus::Result<()> {
+    let Some(id) = server_id(shown, tag) else {
+        return Ok(());
+    };
+
+    connection.call_method(
+        Some(DESTINATION),
+        PATH,
+        Some(DESTINATION),
+        "CloseNotification",
+        &id,
+    )?;
+
+    Ok(())
+}
+
+fn server_id(shown: &Shown, tag: &str) -> Option<u32> {
+    shown
+        .lock()
+        .expect("Failed to lock notifications")
+        .iter()
+        .find(|(_, shown_tag)| shown_tag.as_str() == tag)
+        .map(|(id, _)| *id)
+}
+
+/// Forwards clicks and dismissals of our notifications to the page.
+fn listen(connection: &Connection, shown: &Shown) -> zbus::Result<()> {
+    let proxy = Proxy::new(connection, DESTINATION, PATH, DESTINATION)?;
+
+    let actions = proxy.receive_signal("ActionInvoked")?;
+    {
+        let shown = shown.clone();
+        std::thread::spawn(move || {
+            for signal in actions {
+                let Ok((id, action)) = signal.body().deserialize::<(u32, String)>() else {
+                    continue;
+                };
+
+                let tag = shown
+                    .lock()
+                    .expect("Failed to lock notifications")
+                    .get(&id)
+                    .cloned();
+
+                if let (Some(tag), "default") = (tag, action.as_str()) {
+                    super::bridge::notification_event(&tag, "click");
+                }
+            }
+        });
+    }
+
+    for signal in proxy.receive_signal("NotificationClosed")? {
+        let Ok((id, _reason)) = signal.body().deserialize::<(u32, u32)>() else {
+            continue;
+        };
+
+        let tag = shown
+            .lock()
+            .expect("Failed to lock notifications")
+            .remove(&id);
+
+        if let Some(tag) = tag {
+            super::bridge::notification_event(&tag, "close");
+        }
+    }
+
+    Ok(())
+}
+
+/// Name and icon of the web app, so notifications don't show up as the generic webview.
+fn app_identity() -> (String, String) {
+    let args = WebviewArgs::parse();
+
+    match WebAppLauncher::from_appid(&args.id) {
+        Some(launcher) => (launcher.name, launcher.icon.path.display().to_string()),
+        None => (args.id, String::from(APP_ID)),
+    }
+}
@@ -0,0 +1,424 @@
+//! Site permission requests. Stored decisions are applied right away, others
+//! are asked for with a prompt shown on top of the page. The prompt is native, so
+//! the page can neither see nor answer it.
+
+use cef::*;
+use std::{cell::RefCell, collections::HashMap};
+use webapps::{
+    fl,
+    permissions::{Decision, Permission, SitePermissions},
+};
+
+use super::config_for;
+
+const BACKGROUND_COLOR: u32 = 0xFF2A_2A2A;
+const TEXT_COLOR: u32 = 0xFFF2_F2F2;
+
+// button ids of the prompt
+const ANSWER_ALLOW: i32 = 1;
+const ANSWER_ALWAYS_ALLOW: i32 = 2;
+const ANSWER_DENY: i32 = 3;
+const ANSWER_ALWAYS_DENY: i32 = 4;
+
+const MEDIA_DEVICE_AUDIO: u32 =
+    sys::cef_media_access_permission_types_t::CEF_MEDIA_PERMISSION_DEVICE_AUDIO_CAPTURE as u32;
+const MEDIA_DEVICE_VIDEO: u32 =
+    sys::cef_media_access_permission_types_t::CEF_MEDIA_PERMISSION_DEVICE_VIDEO_CAPTURE as u32;
+const MEDIA_DESKTOP_AUDIO: u32 =
+    sys::cef_media_access_permission_types_t::CEF_MEDIA_PERMISSION_DESKTOP_AUDIO_CAPTURE as u32;
+const MEDIA_DESKTOP_VIDEO: u32 =
+    sys::cef_media_access_permission_types_t::CEF_MEDIA_PERMISSION_DESKTOP_VIDEO_CAPTURE as u32;
+
+const PROMPT_CAMERA: u32 =
+    sys::cef_permission_request_types_t::CEF_PERMISSION_TYPE_CAMERA_STREAM as u32;
+const PROMPT_MICROPHONE: u32 =
+    sys::cef_permission_request_types_t::CEF_PERMISSION_TYPE_MIC_STREAM as u32;
+const PROMPT_GEOLOCATION: u32 =
+    sys::cef_permission_request_types_t::CEF_PERMISSION_TYPE_GEOLOCATION as u32;
+const PROMPT_CLIPBOARD: u32 =
+    sys::cef_permission_request_types_t::CEF_PERMISSION_TYPE_CLIPBOARD as u32;
+const PROMPT_NOTIFICATIONS: u32 =
+    sys::cef_permission_request_types_t::CEF_PERMISSION_TYPE_NOTIFICATIONS as u32;
+
+enum PendingCallback {
+    Media {
+        callback: MediaAccessCallback,
+        mask: u32,
+    },
+    Prompt(PermissionPromptCallback),
+}
+
+struct PendingRequest {
+    app_id: String,
+    origin: String,
+    permissions: Vec<Permission>,
+    callback: PendingCallback,
+    overlay: OverlayController,
+}
+
+// permission handler and button callbacks all run on the UI thread, requests are kept
+// by the identifier of the browser asking
+thread_local! {
+    static PENDING: RefCell<HashMap<i32, HashMap<u64, PendingRequest>>> =
+        RefCell::new(HashMap::new());
+    static NEXT_ID: RefCell<u64> = const { RefCell::new(1) };
+}
+
+fn media_permissions(mask: u32) -> Vec<Permission> {
+    let mut permissions = Vec::new();
+
+    if mask & MEDIA_DEVICE_VIDEO != 0 {
+        permissions.push(Permission::Camera);
+    }
+    if mask & MEDIA_DEVICE_AUDIO != 0 {
+        permissions.push(Permission::Microphone);
+    }
+    if mask & (MEDIA_DESKTOP_AUDIO | MEDIA_DESKTOP_VIDEO) != 0 {
+        permissions.push(Permission::ScreenCapture);
+    }
+
+    permissions
+}
+
+/// Maps a prompt request, `None` when it asks for something we don't manage.
+fn prompt_permissions(mask: u32) -> Option<Vec<Permission>> {
+    let known = PROMPT_CAMERA | PROMPT_MICROPHONE | PROMPT_GEOLOCATION | PROMPT_CLIPBOARD;
+
+    if mask == 0 || mask & !known != 0 {
+        return None;
+    }
+
+    let mut permissions = Vec::new();
+
+    if mask & PROMPT_CAMERA != 0 {
+        permissions.push(Permission::Camera);
+    }
+    if mask & PROMPT_MICROPHONE != 0 {
+        permissions.push(Permission::Microphone);
+    }
+    if mask & PROMPT_GEOLOCATION != 0 {
+        permissions.push(Permission::Geolocation);
+    }
+    if mask & PROMPT_CLIPBOARD != 0 {
+        permissions.push(Permission::Clipboard);
+    }
+
+    Some(permissions)
+}
+
+fn normalize_origin(origin: &str) -> String {
+    origin.trim_end_matches('/').to_string()
+}
+
+fn stored_decision(app_id: &str, origin: &str, permissions: &[Permission]) -> Option<Decision> {
+    SitePermissions::load(app_id).resolve(origin, permissions)
+}
+
+fn remember(app_id: &str, origin: &str, permissions: &[Permission], decision: Decision) {
+    let mut site_permissions = SitePermissions::load(app_id);
+
+    for permission in permissions {
+        site_permissions.set(origin, *permission, decision);
+    }
+
+    if let Err(e) = site_permissions.save(app_id) {
+        eprintln!("Failed to save permissions: {}", e);
+    }
+}
+
+fn resolve(callback: PendingCallback, allow: bool) {
+    match callback {
+        PendingCallback::Media { callback, mask } => {
+            if allow {
+                callback.cont(mask);
+            } else {
+                callback.cancel();
+            }
+        }
+        PendingCallback::Prompt(callback) => {
+            let result = if allow {
+                PermissionRequestResult::ACCEPT
+            } else {
+                PermissionRequestResult::DENY
+            };
+            callback.cont(result);
+        }
+    }
+}
+
+wrap_button_delegate! {
+    struct PromptButtonDelegate {
+        browser_id: i32,
+        id: u64,
+    }
+
+    impl ViewDelegate {}
+
+    impl ButtonDelegate {
+        fn on_button_pressed(&self, button: Option<&mut Button>) {
+            let Some(button) = button else {
+                return;
+            };
+
+            let (allow, remember_decision) = match button.id() {
+                ANSWER_ALLOW => (true, false),
+                ANSWER_ALWAYS_ALLOW => (true, true),
+                ANSWER_ALWAYS_DENY => (false, true),
+                _ => (false, false),
+            };
+
+            decide(self.browser_id, self.id, allow, remember_decision);
+        }
+    }
+}
+
+fn prompt_button(browser_id: i32, id: u64, answer: i32, label: String) -> Option<View> {
+    let mut delegate = PromptButtonDelegate::new(browser_id, id);
+    let button = label_button_create(Some(&mut delegate), Some(&CefString::from(label.as_str())))?;
+    button.set_id(answer);
+
+    Some(View::from(&button))
+}
+
+/// Shows the prompt for a request on top of the page. Returns `false` when there was
+/// no window to show it in.
+fn ask(
+    browser: Option<&mut Browser>,
+    app_id: String,
+    origin: String,
+    permissions: Vec<Permission>,
+    callback: PendingCallback,
+) -> bool {
+    let Some(browser) = browser else {
+        return false;
+    };
+    let browser_id = browser.identifier();
+
+    let Some(window) =
+        browser_view_get_for_browser(Some(browser)).and_then(|browser_view| browser_view.window())
+    else {
+        return false;
+    };
+
+    let id = NEXT_ID.with_borrow_mut(|next_id| {
+        let id = *next_id;
+        *next_id += 1;
+        id
+    });
+
+    let Some(panel) = panel_create(None) else {
+        return false;
+    };
+    panel.set_background_color(BACKGROUND_COLOR);
+    panel.set_to_box_layout(Some(&BoxLayoutSettings {
+        horizontal: 0,
+        between_child_spacing: 8,
+        inside_border_insets: Insets {
+            top: 12,
+            left: 12,
+            bottom: 12,
+            right: 12,
+        },
+        ..Default::default()
+    }));
+
+    let names = permissions
+        .iter()
+        .map(Permission::name)
+        .collect::<Vec<String>>()
+        .join(", ");
+    let message = fl!(
+        "permission-prompt",
+        origin = origin.as_str(),
+        permissions = names
+    );
+
+    // a disabled button is the closest Views has to a plain label
+    if let Some(label) = label_button_create(None, Some(&CefString::from(message.as_str()))) {
+        label.set_enabled(0);
+        label.set_text_color(ButtonState::DISABLED, TEXT_COLOR);
+
+        let mut view = View::from(&label);
+        panel.add_child_view(Some(&mut view));
+    }
+
+    let Some(row) = panel_create(None) else {
+        return false;
+    };
+    row.set_to_box_layout(Some(&BoxLayoutSettings {
+        horizontal: 1,
+        between_child_spacing: 4,
+        ..Default::default()
+    }));
+
+    let answers = [
+        (ANSWER_ALLOW, fl!("permission-allow")),
+        (ANSWER_ALWAYS_ALLOW, fl!("permission-always-allow")),
+        (ANSWER_DENY, fl!("permission-deny")),
+        (ANSWER_ALWAYS_DENY, fl!("permission-always-deny")),
+    ];
+
+    for (answer, label) in answers {
+        if let Some(mut view) = prompt_button(browser_id, id, answer, label) {
+            row.add_child_view(Some(&mut view));
+        }
+    }
+
+    let mut view = View::from(&row);
+    panel.add_child_view(Some(&mut view));
+
+    let mut view = View::from(&panel);
+    let Some(overlay) = window.add_overlay_view(Some(&mut view), DockingMode::TOP_LEFT, 1) else {
+        return false;
+    };
+    overlay.size_to_preferred_size();
+    overlay.set_visible(1);
+
+    PENDING.with_borrow_mut(|pending| {
+        pending.entry(browser_id).or_default().insert(
+            id,
+            PendingRequest {
+                app_id,
+                origin,
+                permissions,
+                callback,
+                overlay,
+            },
+        );
+    });
+
+    true
+}
+
+/// Applies the answer given in a prompt.
+fn decide(browser_id: i32, id: u64, allow: bool, remember_decision: bool) {
+    let Some(request) = PENDING.with_borrow_mut(|pending| {
+        let requests = pending.get_mut(&browser_id)?;
+        let request = requests.remove(&id);
+        if requests.is_empty() {
+            pending.remove(&browser_id);
+        }
+        request
+    }) else {
+        return;
+    };
+
+    request.overlay.destroy();
+
+    if remember_decision {
+        let decision = if allow {
+            Decision::Allow
+        } else {
+            Decision::Deny
+        };
+        remember(
+            &request.app_id,
+            &request.origin,
+            &request.permissions,
+            decision,
+        );
+    }
+
+    resolve(request.callback, allow);
+}
+
+/// Denies the requests of the page `browser` navigates away from and closes their
+/// prompts, other windows keep theirs.
+pub fn dismiss_pending(browser: &Browser) {
+    let Some(requests) = PENDING.with_borrow_mut(|pending| pending.remove(&browser.identifier()))
+    else {
+        return;
+    };
+
+    for (_, request) in requests {
+        request.overlay.destroy();
+        resolve(request.callback, false);
+    }
+}
+
+wrap_permission_handler! {
+    pub struct WebviewPermissionHandler {}
+
+    impl PermissionHandler {
+        fn on_request_media_access_permission(
+            &self,
+            browser: Option<&mut Browser>,
+            _frame: Option<&mut Frame>,
+            requesting_origin: Option<&CefString>,
+            requested_permissions: u32,
+            callback: Option<&mut MediaAccessCallback>,
+        ) -> i32 {
+            let Some(callback) = callback else {
+                return 0;
+            };
+
+            let Some(app) = config_for(browser.as_deref()) else {
+                return 0;
+            };
+            let app_id = app.app_id.to_string();
+
+            let origin =
+                normalize_origin(&requesting_origin.map(CefString::to_string).unwrap_or_default());
+            let permissions = media_permissions(requested_permissions);
+
+            if permissions.is_empty() {
+                return 0;
+            }
+
+            let callback = PendingCallback::Media {
+                callback: callback.clone(),
+                mask: requested_permissions,
+            };
+
+            match stored_decision(&app_id, &origin, &permissions) {
+                Some(decision) => {
+                    resolve(callback, decision == Decision::Allow);
+                    1
+                }
+                None => ask(browser, app_id, origin, permissions, callback).into(),
+            }
+        }
+
+        fn on_show_permission_prompt(
+            &self,
+            browser: Option<&mut Browser>,
+            _prompt_id: u64,
+            requesting_origin: Option<&CefString>,
+            requested_permissions: u32,
+            callback: Option<&mut PermissionPromptCallback>,
+        ) -> i32 {
+            let Some(callback) = callback else {
+                return 0;
+            };
+
+            let Some(app) = config_for(browser.as_deref()) else {
+                return 0;
+            };
+            let app_id = app.app_id.to_string();
+
+            // notifications are answered by the bridge, following the web app's setting
+            if requested_permissions == PROMPT_NOTIFICATIONS {
+                let allowed = app.notifications.unwrap_or_default();
+                resolve(PendingCallback::Prompt(callback.clone()), allowed);
+                return 1;
+            }
+
+            // anything else keeps the engine's default behavior
+            let Some(permissions) = prompt_permissions(requested_permissions) else {
+                return 0;
+            };
+
+            let origin =
+                normalize_origin(&requesting_origin.map(CefString::to_string).unwrap_or_default());
+            let callback = PendingCallback::Prompt(callback.clone());
+
+            match stored_decision(&app_id, &origin, &permissions) {
+                Some(decision) => {
+                    resolve(callback, decision == Decision::Allow);
+                    1
+                }
+                None => ask(browser, app_id, origin, permissions, callback).into(),
+            }
+        }
+    }
+}
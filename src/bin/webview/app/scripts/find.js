@@ -0,0 +1,112 @@
+// Find bar shown above the page. Called with the name of the bridge function and the
+// localized labels; searching happens in the browser, which reports back the number
+// of matches.
+(sendFunction, labels) => {
+  const existing = window.__webappsFindBar;
+
+  if (existing) {
+    existing.focus();
+    return;
+  }
+
+  const bridge = window[sendFunction];
+  const send = (message) => bridge(JSON.stringify(message));
+
+  const host = document.createElement("div");
+  const root = host.attachShadow({ mode: "closed" });
+
+  root.innerHTML = `
+    <style>
+      .bar {
+        position: fixed;
+        top: 8px;
+        right: 16px;
+        z-index: 2147483647;
+        display: flex;
+        align-items: center;
+        gap: 8px;
+        padding: 6px 8px;
+        border-radius: 8px;
+        background: #2a2a2a;
+        color: #f2f2f2;
+        font: 13px system-ui, sans-serif;
+        box-shadow: 0 2px 8px rgba(0, 0, 0, 0.4);
+      }
+      input {
+        width: 200px;
+        padding: 4px 8px;
+        border: 1px solid #555;
+        border-radius: 4px;
+        background: #1e1e1e;
+        color: inherit;
+        font: inherit;
+      }
+      button {
+        border: none;
+        background: transparent;
+        color: inherit;
+        font: inherit;
+        cursor: pointer;
+      }
+      .count {
+        min-width: 48px;
+        opacity: 0.7;
+      }
+    </style>
+    <div class="bar" role="search">
+      <input type="search" />
+      <span class="count"></span>
+      <button class="previous">&#x2191;</button>
+      <button class="next">&#x2193;</button>
+      <button class="close">&#x2715;</button>
+    </div>
+  `;
+
+  const input = root.querySelector("input");
+  const count = root.querySelector(".count");
+
+  input.placeholder = labels.find;
+  root.querySelector(".previous").title = labels.previous;
+  root.querySelector(".next").title = labels.next;
+  root.querySelector(".close").title = labels.close;
+
+  const find = (forward, findNext) => {
+    if (input.value) {
+      send({ type: "find", text: input.value, forward, next: findNext });
+    } else {
+      count.textContent = "";
+      send({ type: "clear-find" });
+    }
+  };
+
+  const close = () => {
+    host.remove();
+    delete window.__webappsFindBar;
+    delete window.__webappsFindResult;
+    send({ type: "close-find" });
+  };
+
+  input.addEventListener("input", () => find(true, false));
+  input.addEventListener("keydown", (event) => {
+    if (event.key === "Enter") {
+      find(!event.shiftKey, true);
+    } else if (event.key === "Escape") {
+      close();
+    }
+  });
+  root.querySelector(".previous").addEventListener("click", () => find(false, true));
+  root.querySelector(".next").addEventListener("click", () => find(true, true));
+  root.querySelector(".close").addEventListener("click", close);
+
+  // read-only, so the page can't take over what the browser calls; removed on close
+  const define = (name, value) =>
+    Object.defineProperty(window, name, { value, writable: false, configurable: true });
+
+  define("__webappsFindBar", { focus: () => input.select(), close });
+  define("__webappsFindResult", (active, total) => {
+    count.textContent = total > 0 ? `${active}/${total}` : labels.none;
+  });
+
+  document.documentElement.appendChild(host);
+  input.focus();
+}
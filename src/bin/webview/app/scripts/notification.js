@@ -0,0 +1,125 @@
+// Replaces the Web Notifications API, which the embedded engine doesn't implement,
+// with notifications shown by the desktop. Injected into the main frame on load,
+// which is where notification events are delivered back to.
+(() => {
+  if (window.__webappsNotificationEvent) {
+    return;
+  }
+
+  const bridge = window["%SEND%"];
+  const permission = "%PERMISSION%";
+  const instances = new Map();
+  let nextId = 1;
+
+  const send = (message) => bridge(JSON.stringify(message));
+
+  class WebAppNotification extends EventTarget {
+    constructor(title, options = {}) {
+      super();
+
+      this.id = nextId++;
+      this.title = String(title);
+      this.body = options.body ?? "";
+      this.tag = options.tag ?? "";
+      this.icon = options.icon ?? "";
+      this.data = options.data ?? null;
+      this.onclick = null;
+      this.onclose = null;
+      this.onerror = null;
+      this.onshow = null;
+
+      if (permission !== "granted") {
+        queueMicrotask(() => this.dispatch("error"));
+        return;
+      }
+
+      instances.set(this.id, this);
+      send({
+        type: "notification",
+        id: this.id,
+        title: this.title,
+        body: this.body,
+        tag: this.tag,
+      });
+      queueMicrotask(() => this.dispatch("show"));
+    }
+
+    static get permission() {
+      return permission;
+    }
+
+    static requestPermission(callback) {
+      if (typeof callback === "function") {
+        callback(permission);
+      }
+
+      return Promise.resolve(permission);
+    }
+
+    close() {
+      if (instances.delete(this.id)) {
+        send({ type: "close-notification", id: this.id });
+        this.dispatch("close");
+      }
+    }
+
+    dispatch(type) {
+      const event = new Event(type, { cancelable: true });
+      const handler = this["on" + type];
+
+      if (typeof handler === "function") {
+        handler.call(this, event);
+      }
+
+      this.dispatchEvent(event);
+    }
+  }
+
+  Object.defineProperty(window, "Notification", {
+    value: WebAppNotification,
+    writable: true,
+    configurable: true,
+  });
+
+  // pages showing notifications through their service worker registration, a worker
+  // calling it by itself runs where no script can be injected
+  if (window.ServiceWorkerRegistration) {
+    ServiceWorkerRegistration.prototype.showNotification = function (title, options = {}) {
+      if (permission !== "granted") {
+        return Promise.reject(new TypeError("Notification permission has not been granted"));
+      }
+
+      new WebAppNotification(title, options);
+      return Promise.resolve();
+    };
+
+    ServiceWorkerRegistration.prototype.getNotifications = function (filter = {}) {
+      const shown = [...instances.values()].filter(
+        (notification) => !filter.tag || notification.tag === filter.tag,
+      );
+
+      return Promise.resolve(shown);
+    };
+  }
+
+  // fixed for the lifetime of the page, so the page can't take over what the browser calls
+  const notificationEvent = (id, type) => {
+    const notification = instances.get(id);
+
+    if (!notification) {
+      return;
+    }
+
+    if (type === "close") {
+      instances.delete(id);
+    }
+
+    notification.dispatch(type);
+  };
+
+  Object.defineProperty(window, "__webappsNotificationEvent", {
+    value: notificationEvent,
+    writable: false,
+    configurable: false,
+  });
+})();
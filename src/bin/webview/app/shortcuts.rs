@@ -0,0 +1,157 @@
+//! Navigation actions, bound to the usual browser shortcuts and to the header bar.
+
+use cef::*;
+
+use super::{config_for, find};
+
+// Windows virtual key codes, which CEF uses on every platform
+const VK_ESCAPE: i32 = 0x1B;
+const VK_LEFT: i32 = 0x25;
+const VK_RIGHT: i32 = 0x27;
+const VK_0: i32 = 0x30;
+const VK_F: i32 = 0x46;
+const VK_I: i32 = 0x49;
+const VK_R: i32 = 0x52;
+const VK_NUMPAD0: i32 = 0x60;
+const VK_ADD: i32 = 0x6B;
+const VK_SUBTRACT: i32 = 0x6D;
+const VK_F3: i32 = 0x72;
+const VK_F5: i32 = 0x74;
+const VK_F12: i32 = 0x7B;
+const VK_OEM_PLUS: i32 = 0xBB;
+const VK_OEM_MINUS: i32 = 0xBD;
+
+const CONTROL: u32 = sys::cef_event_flags_t::EVENTFLAG_CONTROL_DOWN as u32;
+const SHIFT: u32 = sys::cef_event_flags_t::EVENTFLAG_SHIFT_DOWN as u32;
+const ALT: u32 = sys::cef_event_flags_t::EVENTFLAG_ALT_DOWN as u32;
+
+/// Zoom levels are exponents of 1.2, half a step is roughly 10%.
+const ZOOM_STEP: f64 = 0.5;
+const ZOOM_MAX: f64 = 5.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Back = 1,
+    Forward,
+    Reload,
+    ReloadIgnoringCache,
+    Find,
+    StopFind,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+    DevTools,
+}
+
+impl Action {
+    /// Action of a header bar button, which carries it as its view id.
+    pub fn from_id(id: i32) -> Option<Self> {
+        [
+            Action::Back,
+            Action::Forward,
+            Action::Reload,
+            Action::ReloadIgnoringCache,
+            Action::Find,
+            Action::StopFind,
+            Action::ZoomIn,
+            Action::ZoomOut,
+            Action::ZoomReset,
+            Action::DevTools,
+        ]
+        .into_iter()
+        .find(|action| *action as i32 == id)
+    }
+
+    fn from_key(key_code: i32, modifiers: u32) -> Option<Self> {
+        let modifiers = modifiers & (CONTROL | SHIFT | ALT);
+
+        Some(match (modifiers, key_code) {
+            (ALT, VK_LEFT) => Action::Back,
+            (ALT, VK_RIGHT) => Action::Forward,
+            (0, VK_F5) | (CONTROL, VK_R) => Action::Reload,
+            (SHIFT, VK_F5) | (CONTROL, VK_F5) => Action::ReloadIgnoringCache,
+            (_, VK_R) if modifiers == CONTROL | SHIFT => Action::ReloadIgnoringCache,
+            (CONTROL, VK_F) | (0, VK_F3) => Action::Find,
+            (0, VK_ESCAPE) => Action::StopFind,
+            (CONTROL, VK_OEM_PLUS | VK_ADD) => Action::ZoomIn,
+            (_, VK_OEM_PLUS) if modifiers == CONTROL | SHIFT => Action::ZoomIn,
+            (CONTROL, VK_OEM_MINUS | VK_SUBTRACT) => Action::ZoomOut,
+            (CONTROL, VK_0 | VK_NUMPAD0) => Action::ZoomReset,
+            (0, VK_F12) => Action::DevTools,
+            (_, VK_I) if modifiers == CONTROL | SHIFT => Action::DevTools,
+            _ => return None,
+        })
+    }
+}
+
+fn zoom_by(host: &BrowserHost, step: f64) {
+    let level = (host.zoom_level() + step).clamp(-ZOOM_MAX, ZOOM_MAX);
+    host.set_zoom_level(level);
+}
+
+fn toggle_dev_tools(host: &BrowserHost) {
+    if host.has_dev_tools() == 1 {
+        host.close_dev_tools();
+        return;
+    }
+
+    // the popup delegate puts the tools into a window of their own
+    let window_info = WindowInfo::default();
+    let settings = BrowserSettings::default();
+    host.show_dev_tools(Some(&window_info), None, Some(&settings), None);
+}
+
+/// Runs `action` for `browser`. Returns `false` when it doesn't apply right now.
+pub fn run(browser: &mut Browser, action: Action) -> bool {
+    let Some(host) = browser.host() else {
+        return false;
+    };
+
+    match action {
+        Action::Back if browser.can_go_back() == 1 => browser.go_back(),
+        Action::Forward if browser.can_go_forward() == 1 => browser.go_forward(),
+        Action::Back | Action::Forward => return false,
+        Action::Reload => browser.reload(),
+        Action::ReloadIgnoringCache => browser.reload_ignore_cache(),
+        Action::Find => find::show_bar(browser),
+        Action::StopFind => return find::stop(browser),
+        Action::ZoomIn => zoom_by(&host, ZOOM_STEP),
+        Action::ZoomOut => zoom_by(&host, -ZOOM_STEP),
+        Action::ZoomReset => host.set_zoom_level(
+            config_for(Some(browser))
+                .map(|app| app.zoom_level())
+                .unwrap_or_default(),
+        ),
+        Action::DevTools => toggle_dev_tools(&host),
+    }
+
+    true
+}
+
+wrap_keyboard_handler! {
+    pub struct ShortcutKeyboardHandler {}
+
+    impl KeyboardHandler {
+        fn on_pre_key_event(
+            &self,
+            browser: Option<&mut Browser>,
+            event: Option<&KeyEvent>,
+            _os_event: *mut u8,
+            _is_keyboard_shortcut: Option<&mut i32>,
+        ) -> i32 {
+            let (Some(browser), Some(event)) = (browser, event) else {
+                return 0;
+            };
+
+            if event.type_ != KeyEventType::RAWKEYDOWN {
+                return 0;
+            }
+
+            let Some(action) = Action::from_key(event.windows_key_code, event.modifiers) else {
+                return 0;
+            };
+
+            run(browser, action).into()
+        }
+    }
+}
@@ -1,14 +1,19 @@
 use cef::{Rect, *};
 use clap::Parser as _;
 use std::cell::RefCell;
-use webapps::WebviewArgs;
+use webapps::{WebviewArgs, launcher::WebAppLauncher, window_state::WindowState};
 
-use super::simple_handler::*;
+use super::{
+    app_config, browser_config, content_filter, emulation, header_bar, register_browser,
+    simple_handler::*, tray, unregister_browser,
+};
 
 wrap_window_delegate! {
     struct SimpleWindowDelegate {
         browser_view: RefCell<Option<BrowserView>>,
+        app: &'static crate::Browser,
         initial_show_state: ShowState,
+        persist_state: bool,
     }
 
     impl ViewDelegate {
@@ -29,8 +34,14 @@ wrap_window_delegate! {
             let (Some(window), Some(browser_view)) = (window, browser_view.as_ref()) else {
                 return;
             };
-            let mut view = View::from(browser_view);
-            window.add_child_view(Some(&mut view));
+
+            // popups and extra windows stay plain
+            if self.persist_state && self.app.header_bar.unwrap_or_default() {
+                header_bar::add_to_window(window, browser_view, self.app.theme_color.as_deref());
+            } else {
+                let mut view = View::from(browser_view);
+                window.add_child_view(Some(&mut view));
+            }
 
             if self.initial_show_state != ShowState::HIDDEN {
                 window.show();
@@ -41,19 +52,27 @@ wrap_window_delegate! {
             1
         }
 
-        fn initial_bounds(&self, window: Option<&mut Window>) -> Rect {
-            let args = WebviewArgs::parse();
-
-            if let Some(browser_config) = crate::Browser::from_appid(&args.id) {
-                let Some(size) = browser_config.window_size else {
-                    return Default::default();
-                };
-
+        fn initial_bounds(&self, _window: Option<&mut Window>) -> Rect {
+            if let Some(state) = self.saved_state() {
+                if state.width > 0 && state.height > 0 {
+                    return Rect {
+                        x: state.x,
+                        y: state.y,
+                        width: state.width,
+                        height: state.height,
+                    };
+                }
+            }
 
-                return Rect {width: size.0 as i32, height: size.1 as i32, ..Default::default()};
+            let Some(size) = self.app.window_size else {
+                return Default::default();
             };
 
-            Default::default()
+            Rect {
+                width: size.0 as i32,
+                height: size.1 as i32,
+                ..Default::default()
+            }
         }
 
         fn on_window_destroyed(&self, _window: Option<&mut Window>) {
@@ -61,7 +80,29 @@ wrap_window_delegate! {
             *browser_view = None;
         }
 
-        fn can_close(&self, _window: Option<&mut Window>) -> i32 {
+        fn can_close(&self, window: Option<&mut Window>) -> i32 {
+            if let Some(window) = window {
+                let remember = self.app.remember_window_state.unwrap_or_default();
+
+                if self.persist_state && remember {
+                    save_window_state(window, self.app.app_id.as_ref());
+                }
+
+                if self.persist_state {
+                    self.save_zoom();
+                }
+
+                // the main window of a background app hides in the tray instead, the tray
+                // belongs to the app the process was started for
+                let owns_tray = browser_config()
+                    .is_some_and(|browser_config| browser_config.app_id == self.app.app_id);
+
+                if self.persist_state && owns_tray && tray::hide_on_close() {
+                    window.hide();
+                    return 0;
+                }
+            }
+
             // Allow the window to close if the browser says it's OK.
             let browser_view = self.browser_view.borrow();
             let browser_view = browser_view.as_ref().expect("BrowserView is None");
@@ -74,21 +115,172 @@ wrap_window_delegate! {
         }
 
         fn initial_show_state(&self, _window: Option<&mut Window>) -> ShowState {
-            self.initial_show_state
+            // an explicitly requested show state wins over the saved one
+            if self.initial_show_state != ShowState::NORMAL {
+                return self.initial_show_state;
+            }
+
+            let state = self.saved_state();
+            let fullscreen_display = self.app.display.as_deref() == Some("fullscreen");
+
+            match state {
+                Some(state) if state.fullscreen => ShowState::FULLSCREEN,
+                Some(state) if state.maximized => ShowState::MAXIMIZED,
+                Some(_) => self.initial_show_state,
+                // the site's manifest asks for fullscreen and no saved state says otherwise
+                None if self.persist_state && fullscreen_display => ShowState::FULLSCREEN,
+                None => self.initial_show_state,
+            }
         }
 
         fn window_runtime_style(&self) -> RuntimeStyle {
             RuntimeStyle::ALLOY
         }
+
+        fn linux_window_properties(
+            &self,
+            _window: Option<&mut Window>,
+            properties: Option<&mut LinuxWindowProperties>,
+        ) -> i32 {
+            let Some(properties) = properties else {
+                return 0;
+            };
+
+            // must match `StartupWMClass` of the desktop entry, so windows are grouped with
+            // the launcher
+            let app_id = CefString::from(self.app.app_id.as_ref());
+            properties.wayland_app_id = app_id.clone();
+            properties.wm_class_class = app_id.clone();
+            properties.wm_class_name = app_id;
+
+            1
+        }
+    }
+}
+
+impl SimpleWindowDelegate {
+    fn saved_state(&self) -> Option<WindowState> {
+        if !self.persist_state || !self.app.remember_window_state.unwrap_or_default() {
+            return None;
+        }
+
+        WindowState::load(self.app.app_id.as_ref())
+    }
+
+    /// Keeps the zoom chosen with the shortcuts for the next start.
+    fn save_zoom(&self) {
+        let browser_view = self.browser_view.borrow();
+        let Some(host) = browser_view
+            .as_ref()
+            .and_then(|browser_view| browser_view.browser())
+            .and_then(|browser| browser.host())
+        else {
+            return;
+        };
+
+        let zoom = crate::Browser::zoom_from_level(host.zoom_level());
+        if zoom == self.app.zoom.unwrap_or(webapps::DEFAULT_ZOOM) {
+            return;
+        }
+
+        let Some(mut launcher) = WebAppLauncher::from_appid(self.app.app_id.as_ref()) else {
+            return;
+        };
+        launcher.browser.zoom = Some(zoom);
+
+        if let Err(e) = launcher.save() {
+            eprintln!("Failed to save zoom: {}", e);
+        }
+    }
+}
+
+fn save_window_state(window: &Window, app_id: &str) {
+    let maximized = window.is_maximized() == 1;
+    let fullscreen = window.is_fullscreen() == 1;
+
+    // bounds of a maximized window are useless once it gets restored, keep the previous ones
+    let state = if maximized || fullscreen {
+        WindowState {
+            maximized,
+            fullscreen,
+            ..WindowState::load(app_id).unwrap_or_default()
+        }
+    } else {
+        let bounds = window.bounds();
+
+        WindowState {
+            x: bounds.x,
+            y: bounds.y,
+            width: bounds.width,
+            height: bounds.height,
+            maximized,
+            fullscreen,
+        }
+    };
+
+    if let Err(e) = state.save(app_id) {
+        eprintln!("Failed to save window state: {}", e);
     }
 }
 
 wrap_browser_view_delegate! {
-    struct SimpleBrowserViewDelegate {}
+    struct SimpleBrowserViewDelegate {
+        app: &'static crate::Browser,
+    }
 
     impl ViewDelegate {}
 
     impl BrowserViewDelegate {
+        fn on_browser_created(
+            &self,
+            _browser_view: Option<&mut BrowserView>,
+            browser: Option<&mut Browser>,
+        ) {
+            let Some(browser) = browser else {
+                return;
+            };
+
+            register_browser(browser, self.app);
+
+            let Some(host) = browser.host() else {
+                return;
+            };
+
+            host.set_zoom_level(self.app.zoom_level());
+
+            // the user agent of the process belongs to the app it was started for
+            let owns_process = browser_config()
+                .is_some_and(|browser_config| browser_config.app_id == self.app.app_id);
+            if !owns_process {
+                emulation::override_user_agent(&host, &self.app.user_agent(version_info(4)));
+            }
+
+            if let Some(device) = self.app.device() {
+                emulation::emulate(&host, device);
+            }
+        }
+
+        fn on_browser_destroyed(
+            &self,
+            _browser_view: Option<&mut BrowserView>,
+            browser: Option<&mut Browser>,
+        ) {
+            if let Some(browser) = browser {
+                unregister_browser(browser);
+            }
+        }
+
+        fn delegate_for_popup_browser_view(
+            &self,
+            _browser_view: Option<&mut BrowserView>,
+            _settings: Option<&BrowserSettings>,
+            _client: Option<&mut Client>,
+            _is_devtools: i32,
+        ) -> Option<BrowserViewDelegate> {
+            // popups belong to the app of the window opening them
+            Some(SimpleBrowserViewDelegate::new(self.app))
+        }
+
         fn on_popup_browser_view_created(
             &self,
             _browser_view: Option<&mut BrowserView>,
@@ -99,7 +291,9 @@ wrap_browser_view_delegate! {
             // creation.
             let mut window_delegate = SimpleWindowDelegate::new(
                 RefCell::new(popup_browser_view.cloned()),
+                self.app,
                 ShowState::NORMAL,
+                false,
             );
             window_create_top_level(Some(&mut window_delegate));
 
@@ -117,6 +311,33 @@ wrap_app! {
     pub struct SimpleApp;
 
     impl App {
+        fn on_before_command_line_processing(
+            &self,
+            process_type: Option<&CefString>,
+            command_line: Option<&mut CommandLine>,
+        ) {
+            // switches of the browser process are passed on to its subprocesses
+            let is_browser_process =
+                process_type.is_none_or(|process_type| process_type.to_string().is_empty());
+            let (true, Some(command_line)) = (is_browser_process, command_line) else {
+                return;
+            };
+
+            if cfg!(feature = "linux-wayland") {
+                // prefers Wayland and falls back to X11 without a Wayland display
+                let platform = if cfg!(feature = "linux-x11") {
+                    "auto"
+                } else {
+                    "wayland"
+                };
+
+                command_line.append_switch_with_value(
+                    Some(&CefString::from("ozone-platform-hint")),
+                    Some(&CefString::from(platform)),
+                );
+            }
+        }
+
         fn browser_process_handler(&self) -> Option<BrowserProcessHandler> {
             Some(SimpleBrowserProcessHandler::new(RefCell::new(None)))
         }
@@ -140,33 +361,16 @@ wrap_browser_process_handler! {
                 *client = Some(SimpleHandlerClient::new(SimpleHandler::new()));
             }
 
-            // Specify CEF browser settings here.
-            let settings = BrowserSettings::default();
-
             let args = WebviewArgs::parse();
 
-            let Some(browser_config) = crate::Browser::from_appid(&args.id) else {
+            let Some(browser_config) = browser_config() else {
                 return;
             };
 
-            let Some(url) = browser_config.url else {
+            let Some(url) = browser_config.start_url(args.requested_url()) else {
                 return;
             };
 
-            let url = CefString::from(url.as_str());
-
-            // Create the BrowserView.
-            let mut client = self.default_client();
-            let mut delegate = SimpleBrowserViewDelegate::new();
-            let browser_view = browser_view_create(
-                client.as_mut(),
-                Some(&url),
-                Some(&settings),
-                None,
-                None,
-                Some(&mut delegate),
-            );
-
             // Optionally configure the initial show state.
             let initial_show_state = CefString::from(
                 &command_line.switch_value(Some(&CefString::from("initial-show-state"))),
@@ -179,12 +383,14 @@ wrap_browser_process_handler! {
                 _ => ShowState::NORMAL,
             };
 
-            // Create the Window. It will show itself after creation.
-            let mut delegate = SimpleWindowDelegate::new(
-                RefCell::new(browser_view),
+            let mut client = self.default_client();
+            create_browser_window(
+                client.as_mut(),
+                browser_config,
+                &url,
                 initial_show_state,
+                true,
             );
-            window_create_top_level(Some(&mut delegate));
         }
 
         fn default_client(&self) -> Option<Client> {
@@ -192,3 +398,79 @@ wrap_browser_process_handler! {
         }
     }
 }
+
+/// Creates a top-level window of `app` showing `url`. It will show itself after creation.
+/// Only the main window persists its geometry, popups and extra windows don't.
+pub fn create_browser_window(
+    client: Option<&mut Client>,
+    app: &'static crate::Browser,
+    url: &str,
+    initial_show_state: ShowState,
+    persist_state: bool,
+) {
+    // Specify CEF browser settings here.
+    let settings = BrowserSettings::default();
+    let url = CefString::from(url);
+
+    // Create the BrowserView.
+    let mut delegate = SimpleBrowserViewDelegate::new(app);
+    let browser_view = browser_view_create(
+        client,
+        Some(&url),
+        Some(&settings),
+        None,
+        None,
+        Some(&mut delegate),
+    );
+
+    // Create the Window.
+    let mut delegate = SimpleWindowDelegate::new(
+        RefCell::new(browser_view),
+        app,
+        initial_show_state,
+        persist_state,
+    );
+    window_create_top_level(Some(&mut delegate));
+}
+
+wrap_task! {
+    pub struct LaunchApp {
+        app_id: String,
+        url: Option<String>,
+    }
+
+    impl Task {
+        fn execute(&self) {
+            debug_assert_ne!(currently_on(ThreadId::UI), 0);
+
+            let Some(handler) = SimpleHandler::instance() else {
+                return;
+            };
+
+            let Some(app) = app_config(&self.app_id) else {
+                eprintln!("Web app {} is not installed", self.app_id);
+                return;
+            };
+
+            content_filter::start_loading(app);
+
+            // the first window of an app is its main window, later ones are extra windows
+            let shown = {
+                let mut handler = handler.lock().expect("Failed to lock handler");
+                handler.show_app_window(&self.app_id)
+            };
+
+            let url = match self.url.as_deref() {
+                Some(url) => url.to_string(),
+                None if shown => return,
+                None => match app.start_url(None) {
+                    Some(url) => url,
+                    None => return,
+                },
+            };
+
+            let mut client = SimpleHandlerClient::new(handler);
+            create_browser_window(Some(&mut client), app, &url, ShowState::NORMAL, !shown);
+        }
+    }
+}
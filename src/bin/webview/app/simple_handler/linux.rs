@@ -1,12 +1,19 @@
 use cef::*;
 
+#[cfg(feature = "linux-x11")]
 fn window_from_browser(browser: Option<&mut Browser>) -> Option<WindowHandle> {
     let window = browser?.host()?.window_handle();
     if window == 0 { None } else { Some(window) }
 }
 
+#[cfg(not(feature = "linux-x11"))]
+pub fn platform_title_change(_browser: Option<&mut Browser>, _title: Option<&CefString>) {
+    // Wayland windows get their title from the Views framework only.
+}
+
+#[cfg(feature = "linux-x11")]
 pub fn platform_title_change(browser: Option<&mut Browser>, _title: Option<&CefString>) {
-    // Retrieve the X11 display shared with Chromium.
+    // Retrieve the X11 display shared with Chromium. It is null when running on Wayland.
     let display = get_xdisplay();
     if display.is_null() {
         return;
@@ -17,7 +24,6 @@ pub fn platform_title_change(browser: Option<&mut Browser>, _title: Option<&CefS
         return;
     };
 
-    #[cfg(feature = "linux-x11")]
     unsafe {
         use std::ffi::{CString, c_char};
         use x11_dl::xlib::*;
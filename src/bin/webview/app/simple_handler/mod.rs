@@ -1,6 +1,17 @@
 use cef::*;
 use std::sync::{Arc, Mutex, OnceLock, Weak};
 
+use super::{
+    bridge, config_for,
+    downloads::WebviewDownloadHandler,
+    error_page,
+    find::FindBarHandler,
+    navigation::{self, NavigationRequestHandler},
+    permissions::WebviewPermissionHandler,
+    shortcuts::ShortcutKeyboardHandler,
+    tray, user_content,
+};
+
 fn get_data_uri(data: &[u8], mime_type: &str) -> String {
     let data = CefString::from(&base64_encode(Some(data)));
     let uri = CefString::from(&uriencode(Some(&data), 0)).to_string();
@@ -11,7 +22,18 @@ mod linux;
 use linux::*;
 
 fn platform_show_window(_browser: Option<&mut Browser>) {
-    todo!("Implement platform_show_window for non-macOS platforms");
+    // The Views framework already shows and activates the window on Linux.
+}
+
+fn show_window(mut browser: Browser) {
+    if let Some(browser_view) = browser_view_get_for_browser(Some(&mut browser)) {
+        // Show the window using the Views framework.
+        if let Some(window) = browser_view.window() {
+            window.show();
+            window.activate();
+        }
+    }
+    platform_show_window(Some(&mut browser));
 }
 
 static SIMPLE_HANDLER_INSTANCE: OnceLock<Weak<Mutex<SimpleHandler>>> = OnceLock::new();
@@ -53,6 +75,13 @@ impl SimpleHandler {
             }
         }
 
+        // the tray shows the unread count of the main window only
+        if let (Some(main_browser), Some(browser)) = (self.browser_list.first(), browser.as_mut()) {
+            if main_browser.is_same(Some(browser)) == 1 {
+                tray::title_changed(&title.map(CefString::to_string).unwrap_or_default());
+            }
+        }
+
         platform_title_change(browser.as_mut(), title);
     }
 
@@ -108,7 +137,7 @@ impl SimpleHandler {
 
     fn on_load_error(
         &mut self,
-        _browser: Option<&mut Browser>,
+        browser: Option<&mut Browser>,
         frame: Option<&mut Frame>,
         error_code: Errorcode,
         error_text: Option<&CefString>,
@@ -121,22 +150,19 @@ impl SimpleHandler {
         if error_code == sys::cef_errorcode_t::ERR_ABORTED {
             return;
         }
-        let error_code = error_code as i32;
 
         let frame = frame.expect("Frame is None");
 
+        // Failing embeds and ads keep the page usable.
+        if frame.is_main() != 1 {
+            return;
+        }
+
         // Display a load error message using a data: URI.
         let error_text = error_text.map(CefString::to_string).unwrap_or_default();
         let failed_url = failed_url.map(CefString::to_string).unwrap_or_default();
-        let data = format!(
-            r#"
-            <html>
-                <body bgcolor="white">
-                    <h2>Failed to load URL {failed_url} with error {error_text} ({error_code}).</h2>
-                </body>
-            </html>
-            "#
-        );
+        let app = config_for(browser.as_deref());
+        let data = error_page::render(app, &failed_url, error_code, &error_text);
 
         let uri = get_data_uri(data.as_bytes(), "text/html");
         let uri = CefString::from(uri.as_str());
@@ -156,17 +182,27 @@ impl SimpleHandler {
             return;
         }
 
-        let Some(mut main_browser) = self.browser_list.first().cloned() else {
-            return;
-        };
+        if let Some(main_browser) = self.browser_list.first().cloned() {
+            show_window(main_browser);
+        }
+    }
 
-        if let Some(browser_view) = browser_view_get_for_browser(Some(&mut main_browser)) {
-            // Show the window using the Views framework.
-            if let Some(window) = browser_view.window() {
-                window.show();
+    /// Shows the first window of the web app `app_id`, which may share the process with
+    /// others through their profile. Returns `false` when the app has no window.
+    pub fn show_app_window(&mut self, app_id: &str) -> bool {
+        debug_assert_ne!(currently_on(ThreadId::UI), 0);
+
+        let browser = self.browser_list.iter().find(|browser| {
+            config_for(Some(browser)).is_some_and(|app| app.app_id.as_ref() == app_id)
+        });
+
+        match browser {
+            Some(browser) => {
+                show_window(browser.clone());
+                true
             }
+            None => false,
         }
-        platform_show_window(Some(&mut main_browser));
     }
 
     pub fn close_all_browsers(&mut self, force_close: bool) {
@@ -191,6 +227,10 @@ impl SimpleHandler {
     pub fn is_closing(&self) -> bool {
         self.is_closing
     }
+
+    pub fn main_browser(&self) -> Option<Browser> {
+        self.browser_list.first().cloned()
+    }
 }
 
 wrap_client! {
@@ -199,6 +239,10 @@ wrap_client! {
     }
 
     impl Client {
+        fn download_handler(&self) -> Option<DownloadHandler> {
+            Some(WebviewDownloadHandler::new())
+        }
+
         fn display_handler(&self) -> Option<DisplayHandler> {
             Some(SimpleHandlerDisplayHandler::new(self.inner.clone()))
         }
@@ -210,6 +254,32 @@ wrap_client! {
         fn load_handler(&self) -> Option<LoadHandler> {
             Some(SimpleHandlerLoadHandler::new(self.inner.clone()))
         }
+
+        fn request_handler(&self) -> Option<RequestHandler> {
+            Some(NavigationRequestHandler::new())
+        }
+
+        fn permission_handler(&self) -> Option<PermissionHandler> {
+            Some(WebviewPermissionHandler::new())
+        }
+
+        fn keyboard_handler(&self) -> Option<KeyboardHandler> {
+            Some(ShortcutKeyboardHandler::new())
+        }
+
+        fn find_handler(&self) -> Option<FindHandler> {
+            Some(FindBarHandler::new())
+        }
+
+        fn on_process_message_received(
+            &self,
+            browser: Option<&mut Browser>,
+            frame: Option<&mut Frame>,
+            _source_process: ProcessId,
+            message: Option<&mut ProcessMessage>,
+        ) -> i32 {
+            bridge::handle_message(browser, frame, message).into()
+        }
     }
 }
 
@@ -232,6 +302,28 @@ wrap_life_span_handler! {
     }
 
     impl LifeSpanHandler {
+        fn on_before_popup(
+            &self,
+            browser: Option<&mut Browser>,
+            _frame: Option<&mut Frame>,
+            _popup_id: i32,
+            target_url: Option<&CefString>,
+            _target_frame_name: Option<&CefString>,
+            _target_disposition: WindowOpenDisposition,
+            _user_gesture: i32,
+            _popup_features: Option<&PopupFeatures>,
+            _window_info: Option<&mut WindowInfo>,
+            _client: Option<&mut Option<Client>>,
+            _settings: Option<&mut BrowserSettings>,
+            _extra_info: Option<&mut Option<DictionaryValue>>,
+            _no_javascript_access: Option<&mut i32>,
+        ) -> i32 {
+            // popups within the app, e.g. sign-in windows, still get their own window
+            let url = target_url.map(CefString::to_string).unwrap_or_default();
+
+            navigation::open_externally(browser.as_deref(), &url).into()
+        }
+
         fn on_after_created(&self, browser: Option<&mut Browser>) {
             let mut inner = self.inner.lock().expect("Failed to lock inner");
             inner.on_after_created(browser);
@@ -255,6 +347,18 @@ wrap_load_handler! {
     }
 
     impl LoadHandler {
+        fn on_load_start(
+            &self,
+            _browser: Option<&mut Browser>,
+            frame: Option<&mut Frame>,
+            _transition_type: TransitionType,
+        ) {
+            if let Some(frame) = frame {
+                bridge::inject(frame);
+                user_content::inject(frame);
+            }
+        }
+
         fn on_load_error(
             &self,
             browser: Option<&mut Browser>,
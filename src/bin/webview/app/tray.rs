@@ -0,0 +1,464 @@
+//! Tray icon through `org.kde.StatusNotifierItem`, which keeps web apps running
+//! in the background once their window is closed.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        OnceLock,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+};
+use webapps::{fl, launcher::WebAppLauncher};
+use zbus::{
+    blocking::{Connection, Proxy, connection},
+    object_server::SignalEmitter,
+    zvariant::{OwnedObjectPath, OwnedValue, StructureBuilder, Value},
+};
+
+use super::{
+    browser_config,
+    notifications::{self, Notification},
+    simple_handler::SimpleHandler,
+};
+
+const WATCHER: &str = "org.kde.StatusNotifierWatcher";
+const WATCHER_PATH: &str = "/StatusNotifierWatcher";
+const ITEM_PATH: &str = "/StatusNotifierItem";
+const MENU_PATH: &str = "/MenuBar";
+
+const MENU_SHOW: i32 = 1;
+const MENU_QUIT: i32 = 2;
+
+enum Command {
+    Unread(u32),
+}
+
+static SENDER: OnceLock<Sender<Command>> = OnceLock::new();
+// a tray host holds the icon right now, without one a hidden window couldn't come back
+static REGISTERED: AtomicBool = AtomicBool::new(false);
+static QUITTING: AtomicBool = AtomicBool::new(false);
+
+/// Shows the tray icon when the web app keeps running in the background.
+pub fn start() {
+    if !browser_config()
+        .is_some_and(|browser_config| browser_config.run_in_background.unwrap_or_default())
+    {
+        return;
+    }
+
+    SENDER.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            if let Err(e) = worker(receiver) {
+                report(&e);
+            }
+        });
+        sender
+    });
+}
+
+/// Whether closing the main window should only hide it.
+pub fn hide_on_close() -> bool {
+    REGISTERED.load(Ordering::Relaxed) && !QUITTING.load(Ordering::Relaxed)
+}
+
+/// Picks up the unread count from page titles like `(3) Inbox`.
+pub fn title_changed(title: &str) {
+    let Some(sender) = SENDER.get() else {
+        return;
+    };
+
+    let _ = sender.send(Command::Unread(unread_count(title)));
+}
+
+fn unread_count(title: &str) -> u32 {
+    title
+        .trim_start()
+        .strip_prefix('(')
+        .and_then(|rest| rest.split_once(')'))
+        .and_then(|(count, _)| count.trim_end_matches('+').parse().ok())
+        .unwrap_or_default()
+}
+
+fn show_window() {
+    if let Some(handler) = SimpleHandler::instance() {
+        let mut handler = handler.lock().expect("Failed to lock handler");
+        handler.show_main_window();
+    }
+}
+
+fn quit() {
+    QUITTING.store(true, Ordering::Relaxed);
+
+    if let Some(handler) = SimpleHandler::instance() {
+        let mut handler = handler.lock().expect("Failed to lock handler");
+        handler.close_all_browsers(false);
+    }
+}
+
+/// Tells the user closing the window quits the app, not only the log.
+fn report(e: &zbus::Error) {
+    eprintln!("Failed to show tray icon: {}", e);
+
+    notifications::send(Notification {
+        tag: Some("tray".to_string()),
+        summary: fl!("tray-unavailable"),
+        body: e.to_string(),
+        progress: None,
+        activatable: true,
+    });
+}
+
+fn worker(receiver: Receiver<Command>) -> zbus::Result<()> {
+    let item = StatusNotifierItem::new();
+
+    let connection = connection::Builder::session()?
+        .serve_at(ITEM_PATH, item)?
+        .serve_at(MENU_PATH, Menu)?
+        .build()?;
+
+    // pids repeat across flatpak sandboxes, the unique connection name doesn't
+    let unique_name = connection
+        .unique_name()
+        .map(|name| name.to_string())
+        .unwrap_or_default();
+    let suffix = unique_name.trim_start_matches(':').replace('.', "_");
+    let mut name = format!(
+        "org.kde.StatusNotifierItem-{}-{}",
+        std::process::id(),
+        suffix
+    );
+
+    // watchers also accept the unique name, when the well-known one can't be owned
+    if let Err(e) = connection.request_name(name.as_str()) {
+        eprintln!("Failed to own {}, using {}: {}", name, unique_name, e);
+        name = unique_name;
+    }
+
+    // subscribed before registering, so a watcher starting in between isn't missed
+    let proxy = Proxy::new(
+        &connection,
+        "org.freedesktop.DBus",
+        "/org/freedesktop/DBus",
+        "org.freedesktop.DBus",
+    )?;
+    let owner_changes = proxy.receive_signal_with_args("NameOwnerChanged", &[(0, WATCHER)])?;
+    {
+        let connection = connection.clone();
+        let name = name.clone();
+        std::thread::spawn(move || {
+            for signal in owner_changes {
+                let Ok((_, _, new_owner)) = signal.body().deserialize::<(String, String, String)>()
+                else {
+                    continue;
+                };
+
+                // the watcher goes away and comes back when the panel restarts
+                if new_owner.is_empty() {
+                    REGISTERED.store(false, Ordering::Relaxed);
+                } else if let Err(e) = register(&connection, &name) {
+                    eprintln!("Failed to show tray icon: {}", e);
+                }
+            }
+        });
+    }
+
+    // closing the window keeps quitting the app until a watcher shows up
+    if let Err(e) = register(&connection, &name) {
+        report(&e);
+    }
+
+    for command in receiver {
+        let result = match command {
+            Command::Unread(count) => set_unread(&connection, count),
+        };
+
+        if let Err(e) = result {
+            eprintln!("Failed to update tray icon: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+fn register(connection: &Connection, name: &str) -> zbus::Result<()> {
+    connection.call_method(
+        Some(WATCHER),
+        WATCHER_PATH,
+        Some(WATCHER),
+        "RegisterStatusNotifierItem",
+        &name,
+    )?;
+    REGISTERED.store(true, Ordering::Relaxed);
+
+    Ok(())
+}
+
+fn set_unread(connection: &Connection, count: u32) -> zbus::Result<()> {
+    let item = connection
+        .object_server()
+        .interface::<_, StatusNotifierItem>(ITEM_PATH)?;
+
+    if item.get().unread == count {
+        return Ok(());
+    }
+    item.get_mut().unread = count;
+
+    let status = item.get().status();
+    let emitter = item.signal_emitter();
+
+    zbus::block_on(async {
+        StatusNotifierItem::new_title(emitter).await?;
+        StatusNotifierItem::new_tool_tip(emitter).await?;
+        StatusNotifierItem::new_status(emitter, &status).await
+    })
+}
+
+struct StatusNotifierItem {
+    id: String,
+    name: String,
+    icon_name: String,
+    icon_theme_path: String,
+    unread: u32,
+}
+
+impl StatusNotifierItem {
+    fn new() -> Self {
+        let config = browser_config();
+        let id = config
+            .map(|browser_config| browser_config.app_id.as_ref().to_string())
+            .unwrap_or_default();
+        let launcher = WebAppLauncher::from_appid(&id);
+
+        // hosts look the icon up by name, in the folder of the launcher icon
+        let icon_path = launcher.as_ref().map(|launcher| launcher.icon.path.clone());
+        let icon_name = icon_path
+            .as_ref()
+            .and_then(|path| path.file_stem())
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| id.clone());
+        let icon_theme_path = icon_path
+            .as_ref()
+            .and_then(|path| path.parent())
+            .map(|parent| parent.display().to_string())
+            .unwrap_or_default();
+
+        Self {
+            name: launcher
+                .map(|launcher| launcher.name)
+                .unwrap_or_else(|| id.clone()),
+            id,
+            icon_name,
+            icon_theme_path,
+            unread: 0,
+        }
+    }
+}
+
+#[zbus::interface(name = "org.kde.StatusNotifierItem")]
+impl StatusNotifierItem {
+    #[zbus(property)]
+    fn category(&self) -> &str {
+        "Communications"
+    }
+
+    #[zbus(property)]
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    #[zbus(property)]
+    fn title(&self) -> String {
+        match self.unread {
+            0 => self.name.clone(),
+            unread => format!("{} ({})", self.name, unread),
+        }
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> String {
+        match self.unread {
+            0 => String::from("Active"),
+            _ => String::from("NeedsAttention"),
+        }
+    }
+
+    #[zbus(property)]
+    fn icon_name(&self) -> &str {
+        &self.icon_name
+    }
+
+    #[zbus(property)]
+    fn attention_icon_name(&self) -> &str {
+        &self.icon_name
+    }
+
+    #[zbus(property)]
+    fn icon_theme_path(&self) -> &str {
+        &self.icon_theme_path
+    }
+
+    #[zbus(property)]
+    fn tool_tip(&self) -> (String, Vec<(i32, i32, Vec<u8>)>, String, String) {
+        let text = match self.unread {
+            0 => String::new(),
+            unread => fl!("tray-unread", count = unread),
+        };
+
+        (self.icon_name.clone(), Vec::new(), self.name.clone(), text)
+    }
+
+    #[zbus(property)]
+    fn item_is_menu(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn menu(&self) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(MENU_PATH).expect("Invalid menu path")
+    }
+
+    fn activate(&self, _x: i32, _y: i32) {
+        show_window();
+    }
+
+    fn secondary_activate(&self, _x: i32, _y: i32) {
+        show_window();
+    }
+
+    #[zbus(signal)]
+    async fn new_title(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn new_tool_tip(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn new_status(emitter: &SignalEmitter<'_>, status: &str) -> zbus::Result<()>;
+}
+
+fn owned<'a>(value: impl Into<Value<'a>>) -> OwnedValue {
+    value
+        .into()
+        .try_to_owned()
+        .expect("Menu values carry no file descriptors")
+}
+
+fn menu_item(label: String) -> HashMap<String, OwnedValue> {
+    HashMap::from([
+        (String::from("label"), owned(label)),
+        (String::from("enabled"), owned(true)),
+        (String::from("visible"), owned(true)),
+    ])
+}
+
+fn menu_items() -> Vec<(i32, HashMap<String, OwnedValue>)> {
+    vec![
+        (MENU_SHOW, menu_item(fl!("tray-show"))),
+        (MENU_QUIT, menu_item(fl!("tray-quit"))),
+    ]
+}
+
+/// The two entry context menu of the tray icon, through `com.canonical.dbusmenu`.
+struct Menu;
+
+#[zbus::interface(name = "com.canonical.dbusmenu")]
+impl Menu {
+    #[zbus(property)]
+    fn version(&self) -> u32 {
+        3
+    }
+
+    #[zbus(property)]
+    fn text_direction(&self) -> &str {
+        "ltr"
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> &str {
+        "normal"
+    }
+
+    #[zbus(property)]
+    fn icon_theme_path(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn get_layout(
+        &self,
+        _parent_id: i32,
+        _recursion_depth: i32,
+        _property_names: Vec<String>,
+    ) -> zbus::fdo::Result<(u32, (i32, HashMap<String, OwnedValue>, Vec<OwnedValue>))> {
+        let mut children = Vec::new();
+
+        for (id, properties) in menu_items() {
+            let child = StructureBuilder::new()
+                .add_field(id)
+                .add_field(properties)
+                .add_field(Vec::<OwnedValue>::new())
+                .build()
+                .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+
+            children.push(owned(child));
+        }
+
+        let root = HashMap::from([(String::from("children-display"), owned("submenu"))]);
+
+        Ok((1, (0, root, children)))
+    }
+
+    fn get_group_properties(
+        &self,
+        ids: Vec<i32>,
+        _property_names: Vec<String>,
+    ) -> Vec<(i32, HashMap<String, OwnedValue>)> {
+        menu_items()
+            .into_iter()
+            .filter(|(id, _)| ids.is_empty() || ids.contains(id))
+            .collect()
+    }
+
+    fn get_property(&self, id: i32, name: String) -> zbus::fdo::Result<OwnedValue> {
+        menu_items()
+            .into_iter()
+            .find(|(item_id, _)| *item_id == id)
+            .and_then(|(_, mut properties)| properties.remove(&name))
+            .ok_or_else(|| zbus::fdo::Error::InvalidArgs(format!("no property {} on {}", name, id)))
+    }
+
+    fn event(&self, id: i32, event_id: String, _data: OwnedValue, _timestamp: u32) {
+        if event_id != "clicked" {
+            return;
+        }
+
+        match id {
+            MENU_SHOW => show_window(),
+            MENU_QUIT => quit(),
+            _ => {}
+        }
+    }
+
+    fn event_group(&self, events: Vec<(i32, String, OwnedValue, u32)>) -> Vec<i32> {
+        for (id, event_id, data, timestamp) in events {
+            self.event(id, event_id, data, timestamp);
+        }
+
+        Vec::new()
+    }
+
+    fn about_to_show(&self, _id: i32) -> bool {
+        false
+    }
+
+    fn about_to_show_group(&self, _ids: Vec<i32>) -> (Vec<i32>, Vec<i32>) {
+        (Vec::new(), Vec::new())
+    }
+
+    #[zbus(signal)]
+    async fn layout_updated(
+        emitter: &SignalEmitter<'_>,
+        revision: u32,
+        parent: i32,
+    ) -> zbus::Result<()>;
+}
@@ -0,0 +1,78 @@
+//! User stylesheets and scripts configured per web app.
+
+use cef::*;
+
+use super::config_for;
+
+/// Adds the user stylesheet and script to a frame which starts loading a page of the app.
+pub fn inject(frame: &mut Frame) {
+    let Some(browser_config) = config_for(frame.browser().as_ref()) else {
+        return;
+    };
+
+    let url = CefString::from(&frame.url()).to_string();
+
+    if !browser_config.owns_url(&url) {
+        return;
+    }
+
+    let mut script = String::new();
+
+    if let Some(css) = browser_config
+        .user_css
+        .as_deref()
+        .filter(|css| !css.trim().is_empty())
+    {
+        // the document may not have an element yet when loading starts, the style goes
+        // in as soon as it has one, before anything is painted
+        script.push_str(&format!(
+            "(() => {{\
+                const style = document.createElement(\"style\");\
+                style.dataset.webapps = \"user-css\";\
+                style.textContent = {};\
+                const append = () => {{\
+                    (document.head || document.documentElement).append(style);\
+                }};\
+                if (document.documentElement) {{\
+                    append();\
+                }} else {{\
+                    new MutationObserver((_, observer) => {{\
+                        if (document.documentElement) {{\
+                            observer.disconnect();\
+                            append();\
+                        }}\
+                    }}).observe(document, {{ childList: true }});\
+                }}\
+            }})();",
+            serde_json::json!(css)
+        ));
+    }
+
+    if let Some(js) = browser_config
+        .user_js
+        .as_deref()
+        .filter(|js| !js.trim().is_empty())
+    {
+        // the page's own scripts run first, like with a userscript manager
+        script.push_str(&format!(
+            "(() => {{\
+                const run = () => {{ {js}\n}};\
+                if (document.readyState === \"loading\") {{\
+                    document.addEventListener(\"DOMContentLoaded\", run, {{ once: true }});\
+                }} else {{\
+                    run();\
+                }}\
+            }})();"
+        ));
+    }
+
+    if script.is_empty() {
+        return;
+    }
+
+    frame.execute_java_script(
+        Some(&CefString::from(script.as_str())),
+        Some(&CefString::from(url.as_str())),
+        0,
+    );
+}
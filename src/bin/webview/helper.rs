@@ -1,6 +1,13 @@
 use cef::*;
 
+mod renderer;
+
 fn main() {
     let args = args::Args::new();
-    let _ = execute_process(Some(args.as_main_args()), None, std::ptr::null_mut());
+    let mut app = renderer::HelperApp::new();
+    let _ = execute_process(
+        Some(args.as_main_args()),
+        Some(&mut app),
+        std::ptr::null_mut(),
+    );
 }
@@ -1,7 +1,10 @@
 pub mod app;
+use i18n_embed::DesktopLanguageRequester;
 use webapps::browser::Browser;
 
 fn main() -> Result<(), &'static str> {
+    init_localizer();
+
     let _library = app::load_cef();
 
     let args = cef::args::Args::new();
@@ -14,3 +17,12 @@ fn main() -> Result<(), &'static str> {
 
     Ok(())
 }
+
+fn init_localizer() {
+    let localizer = webapps::localize::localizer();
+    let requested_languages = DesktopLanguageRequester::requested_languages();
+
+    if let Err(why) = localizer.select(&requested_languages) {
+        eprintln!("error while loading fluent localizations: {}", why);
+    }
+}
@@ -0,0 +1,92 @@
+//! Renderer side of the bridge. The main frame of every page gets a native
+//! function which hands the bridge scripts' messages to the browser process,
+//! where the sending frame is known and checked.
+
+use cef::*;
+use webapps::{BRIDGE_FUNCTION, BRIDGE_MESSAGE};
+
+wrap_app! {
+    pub struct HelperApp;
+
+    impl App {
+        fn render_process_handler(&self) -> Option<RenderProcessHandler> {
+            Some(BridgeRenderProcessHandler::new())
+        }
+    }
+}
+
+wrap_render_process_handler! {
+    struct BridgeRenderProcessHandler {}
+
+    impl RenderProcessHandler {
+        fn on_context_created(
+            &self,
+            _browser: Option<&mut Browser>,
+            frame: Option<&mut Frame>,
+            context: Option<&mut V8Context>,
+        ) {
+            let (Some(frame), Some(context)) = (frame, context) else {
+                return;
+            };
+
+            // embedded frames, ads included, get no way to talk to the webview
+            if frame.is_main() != 1 {
+                return;
+            }
+
+            let Some(global) = context.global() else {
+                return;
+            };
+
+            let name = CefString::from(BRIDGE_FUNCTION);
+            let mut handler = BridgeHandler::new();
+            let Some(mut function) = v8_value_create_function(Some(&name), Some(&mut handler))
+            else {
+                return;
+            };
+
+            global.set_value_bykey(Some(&name), Some(&mut function), Default::default());
+        }
+    }
+}
+
+wrap_v8_handler! {
+    struct BridgeHandler {}
+
+    impl V8Handler {
+        fn execute(
+            &self,
+            _name: Option<&CefString>,
+            _object: Option<&mut V8Value>,
+            arguments: Option<&[Option<V8Value>]>,
+            _retval: Option<&mut Option<V8Value>>,
+            _exception: Option<&mut CefString>,
+        ) -> i32 {
+            let Some(payload) = arguments
+                .and_then(|arguments| arguments.first())
+                .and_then(Option::as_ref)
+                .filter(|argument| argument.is_string() == 1)
+                .map(|argument| CefString::from(&argument.string_value()))
+            else {
+                return 0;
+            };
+
+            let Some(frame) = v8_context_get_current_context().and_then(|context| context.frame())
+            else {
+                return 0;
+            };
+            let Some(mut message) = process_message_create(Some(&CefString::from(BRIDGE_MESSAGE)))
+            else {
+                return 0;
+            };
+
+            if let Some(arguments) = message.argument_list() {
+                arguments.set_string(0, Some(&payload));
+            }
+
+            frame.send_process_message(ProcessId::BROWSER, Some(&mut message));
+
+            1
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use rand::{RngExt as _, rng};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -9,8 +10,94 @@ pub struct Browser {
     pub window_title: Option<String>,
     pub url: Option<String>,
     pub profile: PathBuf,
+    pub isolated: Option<bool>,
     pub window_size: Option<crate::WindowSize>,
     pub try_simulate_mobile: Option<bool>,
+    /// URL patterns like `https://mail.example.com/*` opened in this web app
+    pub url_handlers: Option<Vec<String>>,
+    /// Pages of the app opening links of other schemes, like `mailto:` ones
+    pub protocol_handlers: Option<Vec<ProtocolHandler>>,
+    /// Restore the last window geometry instead of `window_size`
+    pub remember_window_state: Option<bool>,
+    pub download_dir: Option<PathBuf>,
+    /// Origins like `https://accounts.example.com` kept in the app besides its own one
+    pub allowed_origins: Option<Vec<String>>,
+    /// Let the page show desktop notifications
+    pub notifications: Option<bool>,
+    /// Stylesheet added to every page of the app's own origin
+    pub user_css: Option<String>,
+    /// Script run on every page of the app's own origin once its document is ready
+    pub user_js: Option<String>,
+    /// Block requests matching the filter lists
+    pub content_blocking: Option<bool>,
+    /// Hosts like `example.com` whose pages are never filtered
+    pub blocking_allowlist: Option<Vec<String>>,
+    /// `theme_color` of the site's web app manifest
+    pub theme_color: Option<String>,
+    /// `display` mode of the site's web app manifest, `fullscreen` starts the window fullscreen
+    pub display: Option<String>,
+    /// Show back, forward, reload and find buttons above the page
+    pub header_bar: Option<bool>,
+    /// Page zoom in percent, see `DEFAULT_ZOOM`
+    pub zoom: Option<u32>,
+    /// Replaces the default user agent, `{chrome}` stands for the bundled Chrome version
+    pub user_agent: Option<String>,
+    /// Name of the device emulated when simulating mobile, see `device::PRESETS`
+    pub device: Option<String>,
+    /// Hide the window in the tray when it's closed, so the app keeps running
+    pub run_in_background: Option<bool>,
+}
+
+/// Like a `protocol_handlers` entry of a web app manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ProtocolHandler {
+    /// Scheme like `mailto`
+    pub protocol: String,
+    /// Page opening a link, `%s` stands for the escaped link. Relative to the app's url.
+    pub url: String,
+}
+
+impl ProtocolHandler {
+    /// Parses `mailto=https://mail.example.com/compose?to=%s`. The scheme can't be
+    /// http(s), those links are claimed with URL patterns.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (protocol, url) = value.split_once('=')?;
+        let protocol = crate::url_handler::pattern_scheme(&format!("{}:", protocol.trim()))?;
+        let url = url.trim();
+
+        let valid = !matches!(protocol.as_str(), "http" | "https") && url.contains("%s");
+
+        valid.then(|| Self {
+            protocol,
+            url: url.to_string(),
+        })
+    }
+}
+
+/// Id for a new web app. It names files and directories, so only letters and
+/// digits of `name` are kept.
+pub fn app_id_from_name(name: &str) -> String {
+    let mut app_id = name
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>();
+
+    if app_id.is_empty() {
+        app_id = String::from("WebApp");
+    }
+
+    app_id + &rng().random_range(1000..10000).to_string()
+}
+
+/// Turns user input into a name usable as a profile directory.
+pub fn profile_name(name: &str) -> String {
+    let name = name.trim().replace(['/', '\\'], "-");
+
+    if name.is_empty() || name.chars().all(|c| c == '.') {
+        return String::from(crate::DEFAULT_SHARED_PROFILE);
+    }
+
+    name
 }
 
 impl Browser {
@@ -21,15 +108,70 @@ impl Browser {
         Self {
             app_id: crate::WebviewArgs {
                 id: app_id.to_string(),
+                url_flag: None,
+                url: None,
             },
             window_title: None,
             url: None,
             profile: path,
+            isolated: Some(true),
             window_size: None,
             try_simulate_mobile: None,
+            url_handlers: None,
+            protocol_handlers: None,
+            remember_window_state: None,
+            download_dir: None,
+            allowed_origins: None,
+            notifications: None,
+            user_css: None,
+            user_js: None,
+            content_blocking: None,
+            blocking_allowlist: None,
+            theme_color: None,
+            display: None,
+            header_bar: None,
+            zoom: None,
+            user_agent: None,
+            device: None,
+            run_in_background: None,
         }
     }
 
+    /// Zoom as a CEF zoom level, which counts steps of 20% from 100%.
+    pub fn zoom_level(&self) -> f64 {
+        let zoom = self.zoom.unwrap_or(crate::DEFAULT_ZOOM).max(1);
+
+        (zoom as f64 / crate::DEFAULT_ZOOM as f64).ln() / 1.2f64.ln()
+    }
+
+    /// Page zoom in percent for a CEF zoom level, the inverse of `zoom_level`.
+    pub fn zoom_from_level(level: f64) -> u32 {
+        (crate::DEFAULT_ZOOM as f64 * 1.2f64.powf(level)).round() as u32
+    }
+
+    /// The emulated device, `None` unless simulating mobile.
+    pub fn device(&self) -> Option<&'static crate::device::Device> {
+        if !self.try_simulate_mobile.unwrap_or_default() {
+            return None;
+        }
+
+        Some(crate::device::Device::find(self.device.as_deref()))
+    }
+
+    /// User agent for the bundled Chrome `chrome_major`, the override or the one
+    /// of the emulated device.
+    pub fn user_agent(&self, chrome_major: i32) -> String {
+        let user_agent = match self.user_agent.as_deref().map(str::trim) {
+            Some(user_agent) if !user_agent.is_empty() => user_agent,
+            _ => match self.device() {
+                Some(device) => device.user_agent,
+                None => crate::user_agent::DESKTOP,
+            },
+        };
+
+        crate::user_agent::expand(user_agent, chrome_major)
+    }
+
     pub fn from_appid(id: &str) -> Option<Self> {
         if let Some(launcher) = crate::launcher::installed_webapps()
             .iter()
@@ -41,31 +183,245 @@ impl Browser {
         None
     }
 
-    pub fn get_exec(&self) -> Option<String> {
+    /// Command line launching this web app, ready to be quoted into an `Exec` key.
+    pub fn get_exec(&self) -> Option<Vec<String>> {
         let Some(cef_path) = cef_path() else {
             return None;
         };
 
-        Some(format!(
-            "env LD_LIBRARY_PATH={} {}.webview {}",
-            cef_path.display(),
-            crate::APP_ID,
-            self.app_id.as_ref()
-        ))
+        Some(vec![
+            String::from("env"),
+            format!("LD_LIBRARY_PATH={}", cef_path.display()),
+            format!("{}.webview", crate::APP_ID),
+            self.app_id.id.clone(),
+        ])
+    }
+
+    /// Starts the webview, optionally at `url` instead of the web app's start page.
+    pub fn launch(&self, url: Option<&str>) -> anyhow::Result<()> {
+        let Some(exec) = self.get_exec() else {
+            anyhow::bail!("CEF runtime not found");
+        };
+
+        std::process::Command::new(&exec[0])
+            .args(&exec[1..])
+            .args(url)
+            .spawn()?;
+
+        Ok(())
+    }
+
+    /// Page the webview opens first. A requested URL is only honoured when the web
+    /// app opens it, see `resolve_url`.
+    pub fn start_url(&self, requested: Option<&str>) -> Option<String> {
+        if let Some(requested) = requested {
+            if let Some(url) = self.resolve_url(requested) {
+                return Some(url);
+            }
+
+            tracing::warn!("ignoring start url {} outside of the web app", requested);
+        }
+
+        self.url.clone()
+    }
+
+    /// Page showing `requested` in the web app, the URL itself when the app owns it or
+    /// the page of its protocol handler for links like `mailto:`. `None` otherwise.
+    pub fn resolve_url(&self, requested: &str) -> Option<String> {
+        if self.owns_url(requested) {
+            return Some(requested.to_string());
+        }
+
+        let scheme = url::Url::parse(requested).ok()?.scheme().to_string();
+        let handler = self
+            .protocol_handlers
+            .iter()
+            .flatten()
+            .find(|handler| handler.protocol.eq_ignore_ascii_case(&scheme))?;
+
+        let escaped =
+            url::form_urlencoded::byte_serialize(requested.as_bytes()).collect::<String>();
+        let target = handler.url.replace("%s", &escaped);
+        let target = match self.url.as_deref().map(url::Url::parse) {
+            Some(Ok(base)) => base.join(&target).ok()?,
+            _ => url::Url::parse(&target).ok()?,
+        };
+
+        // the page has to be one the webview can load
+        matches!(target.scheme(), "http" | "https").then(|| target.to_string())
+    }
+
+    /// Whether `url` belongs to the web app itself, its origin or one of its URL handlers.
+    pub fn owns_url(&self, url: &str) -> bool {
+        let Ok(requested) = url::Url::parse(url) else {
+            return false;
+        };
+
+        let same_origin = self
+            .url
+            .as_deref()
+            .and_then(|app_url| url::Url::parse(app_url).ok())
+            .is_some_and(|app_url| app_url.origin() == requested.origin());
+
+        same_origin
+            || self
+                .url_handlers
+                .iter()
+                .flatten()
+                .any(|pattern| crate::url_handler::pattern_matches(pattern, url))
+    }
+
+    /// Navigation policy, URLs which don't stay in the web app open in the default browser.
+    pub fn stays_in_app(&self, url: &str) -> bool {
+        let Ok(parsed) = url::Url::parse(url) else {
+            return true;
+        };
+
+        match parsed.scheme() {
+            // pages rendered by the engine itself
+            "about" | "blob" | "chrome" | "data" | "devtools" | "javascript" => return true,
+            "http" | "https" => {}
+            // `mailto:` and friends belong to other applications, links the app handles
+            // itself come back through its protocol handler
+            _ => return false,
+        }
+
+        let origin = parsed.origin().ascii_serialization();
+
+        self.owns_url(url)
+            || self.allowed_origins.iter().flatten().any(|pattern| {
+                crate::url_handler::pattern_matches(pattern.trim_end_matches('/'), &origin)
+            })
+    }
+
+    /// Whether requests made by `page_url` go through the content filter.
+    pub fn filters_page(&self, page_url: &str) -> bool {
+        if !self.content_blocking.unwrap_or_default() {
+            return false;
+        }
+
+        let Some(host) = url::Url::parse(page_url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_lowercase))
+        else {
+            return true;
+        };
+
+        !self.blocking_allowlist.iter().flatten().any(|allowed| {
+            let allowed = allowed.trim().to_lowercase();
+
+            host == allowed || host.ends_with(&format!(".{}", allowed))
+        })
+    }
+
+    /// Folder downloads are saved to, the user's download directory unless set per app.
+    pub fn download_location(&self) -> PathBuf {
+        self.download_dir
+            .clone()
+            .or_else(dirs::download_dir)
+            .or_else(dirs::home_dir)
+            .unwrap_or_default()
+    }
+
+    pub fn is_isolated(&self) -> bool {
+        self.isolated.unwrap_or(true)
+    }
+
+    /// Name of the shared profile, `None` when the profile is isolated.
+    pub fn shared_profile(&self) -> Option<String> {
+        if self.is_isolated() {
+            return None;
+        }
+
+        self.profile
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+    }
+
+    pub fn set_profile(&mut self, isolated: bool, shared_profile: &str) {
+        self.isolated = Some(isolated);
+        self.profile = if isolated {
+            dirs::data_dir()
+                .unwrap_or_default()
+                .join(crate::APP_ID)
+                .join("profiles")
+                .join(self.app_id.as_ref())
+        } else {
+            crate::profiles::Profile::new(shared_profile).path
+        };
     }
 
     pub fn delete(&self) {
-        let xdg_data = dirs::data_dir().unwrap_or_default();
+        crate::window_state::WindowState::delete(self.app_id.as_ref());
+        crate::permissions::SitePermissions::delete(self.app_id.as_ref());
 
-        let path = xdg_data
-            .join(crate::APP_ID)
-            .join("profiles")
-            .join(self.app_id.as_ref());
+        // shared profiles stay around as long as any other web app uses them
+        let in_use = crate::launcher::installed_webapps().iter().any(|launcher| {
+            launcher.browser.app_id != self.app_id && launcher.browser.profile == self.profile
+        });
 
-        if path.exists() {
-            if let Err(e) = std::fs::remove_dir_all(&path) {
+        if in_use {
+            return;
+        }
+
+        if self.profile.exists() {
+            if let Err(e) = std::fs::remove_dir_all(&self.profile) {
                 eprintln!("Failed to delete profile directory: {}", e);
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mail() -> Browser {
+        let mut browser = Browser::new("Mail1234");
+        browser.url = Some(String::from("https://mail.example.com/inbox"));
+        browser.protocol_handlers = Some(vec![ProtocolHandler {
+            protocol: String::from("mailto"),
+            url: String::from("/compose?to=%s"),
+        }]);
+
+        browser
+    }
+
+    #[test]
+    fn resolves_protocol_handlers() {
+        assert_eq!(
+            mail()
+                .resolve_url("mailto:someone@example.com?subject=a b")
+                .as_deref(),
+            Some(
+                "https://mail.example.com/compose?to=mailto%3Asomeone%40example.com%3Fsubject%3Da+b"
+            )
+        );
+        assert_eq!(mail().resolve_url("tel:123"), None);
+    }
+
+    #[test]
+    fn start_url_stays_in_app() {
+        let browser = mail();
+
+        assert_eq!(
+            browser
+                .start_url(Some("https://mail.example.com/sent"))
+                .as_deref(),
+            Some("https://mail.example.com/sent")
+        );
+        assert_eq!(browser.start_url(Some("https://evil.net/")), browser.url);
+        assert_eq!(browser.start_url(Some("tel:123")), browser.url);
+    }
+
+    #[test]
+    fn protocol_handlers_open_web_pages() {
+        let mut browser = mail();
+        browser.protocol_handlers = Some(vec![ProtocolHandler {
+            protocol: String::from("mailto"),
+            url: String::from("javascript:alert('%s')"),
+        }]);
+
+        assert_eq!(browser.resolve_url("mailto:someone@example.com"), None);
+    }
+}
@@ -0,0 +1,344 @@
+use anyhow::Context as _;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+use crate::{
+    Category,
+    browser::Browser,
+    launcher::{DesktopAction, WebAppLauncher, WebappIcon},
+};
+
+pub const BUNDLE_VERSION: u32 = 1;
+pub const BUNDLE_EXTENSION: &str = "webapps.ron";
+
+/// Self-contained set of web apps which can be moved between machines.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Bundle {
+    pub version: u32,
+    pub apps: Vec<BundledApp>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BundledApp {
+    pub name: String,
+    pub category: Category,
+    /// Browser settings with the profile path made relative to the data directory
+    /// and the download folder relative to the home directory.
+    pub browser: Browser,
+    pub icon: BundledIcon,
+    pub comment: Option<String>,
+    pub keywords: Option<Vec<String>>,
+    pub mime_types: Option<Vec<String>>,
+    pub startup_notify: Option<bool>,
+    pub actions: Option<Vec<DesktopAction>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BundledIcon {
+    pub file_name: String,
+    /// Base64 encoded icon file
+    pub data: String,
+}
+
+impl From<&WebAppLauncher> for BundledApp {
+    fn from(launcher: &WebAppLauncher) -> Self {
+        let mut browser = launcher.browser.clone();
+        browser.profile = match browser.shared_profile() {
+            Some(name) => PathBuf::from("shared-profiles").join(name),
+            None => PathBuf::from("profiles").join(browser.app_id.as_ref()),
+        };
+        // folders outside of home likely don't exist on other machines
+        browser.download_dir = browser.download_dir.as_deref().and_then(|dir| {
+            let home = dirs::home_dir()?;
+            Some(dir.strip_prefix(home).ok()?.to_path_buf())
+                .filter(|dir| !dir.as_os_str().is_empty())
+        });
+
+        let file_name = launcher
+            .icon
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| format!("{}.png", launcher.browser.app_id.as_ref()));
+
+        Self {
+            name: launcher.name.clone(),
+            category: launcher.category.clone(),
+            browser,
+            icon: BundledIcon {
+                file_name,
+                data: STANDARD.encode(&launcher.icon.buffer),
+            },
+            comment: launcher.comment.clone(),
+            keywords: launcher.keywords.clone(),
+            mime_types: launcher.mime_types.clone(),
+            startup_notify: launcher.startup_notify,
+            actions: launcher.actions.clone(),
+        }
+    }
+}
+
+/// Paths of a bundle come from an untrusted file, they must stay inside the
+/// directory they get joined to.
+fn check_relative(path: &Path) -> anyhow::Result<()> {
+    let mut components = path.components().peekable();
+
+    if components.peek().is_none() || !components.all(|c| matches!(c, Component::Normal(_))) {
+        anyhow::bail!("bundle contains unsafe path {}", path.display());
+    }
+
+    Ok(())
+}
+
+impl BundledApp {
+    /// Stylesheet or script the app injects into its pages.
+    pub fn has_custom_code(&self) -> bool {
+        [&self.browser.user_css, &self.browser.user_js]
+            .into_iter()
+            .any(|code| code.as_deref().is_some_and(|code| !code.trim().is_empty()))
+    }
+
+    /// Decodes the icon and anchors the profile on this machine, nothing is written
+    /// until the app gets installed.
+    pub fn to_launcher(&self) -> anyhow::Result<WebAppLauncher> {
+        let buffer = STANDARD
+            .decode(&self.icon.data)
+            .context("icon data is not valid base64")?;
+
+        let mut browser = self.browser.clone();
+
+        check_relative(&browser.profile)?;
+        if let Some(download_dir) = &browser.download_dir {
+            check_relative(download_dir)?;
+        }
+        check_relative(Path::new(&self.icon.file_name))?;
+
+        browser.download_dir = match browser.download_dir.take() {
+            Some(dir) => Some(
+                dirs::home_dir()
+                    .context("cannot locate home directory")?
+                    .join(dir),
+            ),
+            None => None,
+        };
+
+        // the id names files and directories, never trust the one of the bundle
+        browser.app_id.id = crate::browser::app_id_from_name(&self.name);
+        browser.app_id.url_flag = None;
+        browser.app_id.url = None;
+
+        let shared_profile = browser
+            .profile
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        browser.set_profile(browser.is_isolated(), &shared_profile);
+
+        let file_name = Path::new(&self.icon.file_name)
+            .file_name()
+            .context("invalid icon file name")?;
+        let icons = crate::icons_location().context("cannot locate icons directory")?;
+        let mut path = icons.join(file_name);
+
+        if path.exists() && std::fs::read(&path)? != buffer {
+            path = icons.join(format!(
+                "{}_{}",
+                browser.app_id.as_ref(),
+                file_name.to_string_lossy()
+            ));
+        }
+
+        let mut launcher = WebAppLauncher::new(
+            browser,
+            self.name.clone(),
+            WebappIcon { path, buffer },
+            self.category.clone(),
+        );
+        launcher.comment = self.comment.clone();
+        launcher.keywords = self.keywords.clone();
+        launcher.mime_types = self.mime_types.clone();
+        launcher.startup_notify = self.startup_notify;
+        launcher.actions = self.actions.clone();
+
+        Ok(launcher)
+    }
+}
+
+impl Bundle {
+    pub fn new(launchers: &[WebAppLauncher]) -> Self {
+        Self {
+            version: BUNDLE_VERSION,
+            apps: launchers.iter().map(BundledApp::from).collect(),
+        }
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        let bundle = ron::from_str::<Self>(&content)?;
+
+        if bundle.version > BUNDLE_VERSION {
+            anyhow::bail!(
+                "bundle version {} is newer than supported version {}",
+                bundle.version,
+                BUNDLE_VERSION
+            );
+        }
+
+        Ok(bundle)
+    }
+
+    pub fn export(&self, path: &Path) -> anyhow::Result<()> {
+        let content = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, content)?;
+
+        Ok(())
+    }
+
+    /// Apps bringing their own stylesheet or script, which the user should see
+    /// before they run.
+    pub fn apps_with_custom_code(&self) -> Vec<&BundledApp> {
+        self.apps
+            .iter()
+            .filter(|app| app.has_custom_code())
+            .collect()
+    }
+
+    /// Drops the stylesheets and scripts of every app.
+    pub fn strip_custom_code(&mut self) {
+        for app in &mut self.apps {
+            app.browser.user_css = None;
+            app.browser.user_js = None;
+        }
+    }
+
+    /// Installs every web app of the bundle through the dynamic launcher portal.
+    pub async fn import(&self) -> Vec<anyhow::Result<WebAppLauncher>> {
+        let mut results = Vec::new();
+
+        for app in &self.apps {
+            results.push(Self::install(app).await);
+        }
+
+        results
+    }
+
+    async fn install(app: &BundledApp) -> anyhow::Result<WebAppLauncher> {
+        let launcher = app.to_launcher()?;
+
+        if !launcher.create().await? {
+            anyhow::bail!("failed to install launcher for {}", launcher.name);
+        }
+
+        // an icon already there may be shared with another app and stays
+        let existing = std::fs::read(&launcher.icon.path).ok();
+        let new_icon = existing.is_none();
+        if existing.as_ref() != Some(&launcher.icon.buffer) {
+            std::fs::write(&launcher.icon.path, &launcher.icon.buffer)?;
+        }
+
+        if let Err(e) = launcher.save() {
+            if new_icon {
+                let _ = std::fs::remove_file(&launcher.icon.path);
+            }
+            return Err(e);
+        }
+
+        Ok(launcher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundled_app(profile: &str, download_dir: Option<&str>, icon: &str) -> BundledApp {
+        let mut browser = Browser::new("Mail1234");
+        browser.profile = PathBuf::from(profile);
+        browser.download_dir = download_dir.map(PathBuf::from);
+
+        BundledApp {
+            name: String::from("Mail"),
+            category: Category::default(),
+            browser,
+            icon: BundledIcon {
+                file_name: String::from(icon),
+                data: STANDARD.encode(b"icon"),
+            },
+            comment: None,
+            keywords: None,
+            mime_types: None,
+            startup_notify: None,
+            actions: None,
+        }
+    }
+
+    #[test]
+    fn accepts_relative_paths() {
+        assert!(check_relative(Path::new("profiles/Mail1234")).is_ok());
+        assert!(check_relative(Path::new("Downloads")).is_ok());
+    }
+
+    #[test]
+    fn rejects_parent_directories() {
+        assert!(check_relative(Path::new("../.ssh")).is_err());
+        assert!(check_relative(Path::new("profiles/../../.ssh")).is_err());
+        assert!(check_relative(Path::new("./profiles")).is_err());
+        assert!(check_relative(Path::new("")).is_err());
+
+        assert!(
+            bundled_app("../../.ssh", None, "mail.png")
+                .to_launcher()
+                .is_err()
+        );
+        assert!(
+            bundled_app("profiles/Mail1234", Some("../.config"), "mail.png")
+                .to_launcher()
+                .is_err()
+        );
+        assert!(
+            bundled_app("profiles/Mail1234", None, "../../.bashrc")
+                .to_launcher()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn rejects_absolute_paths() {
+        assert!(check_relative(Path::new("/home/user/.ssh")).is_err());
+
+        assert!(
+            bundled_app("/home/user/.ssh", None, "mail.png")
+                .to_launcher()
+                .is_err()
+        );
+        assert!(
+            bundled_app("profiles/Mail1234", Some("/etc"), "mail.png")
+                .to_launcher()
+                .is_err()
+        );
+        assert!(
+            bundled_app("profiles/Mail1234", None, "/etc/passwd")
+                .to_launcher()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn finds_custom_code() {
+        let mut plain = bundled_app("profiles/Mail1234", None, "mail.png");
+        plain.browser.user_css = Some(String::from("  \n"));
+        let mut scripted = bundled_app("profiles/Mail1234", None, "mail.png");
+        scripted.browser.user_js = Some(String::from("alert(1)"));
+
+        let mut bundle = Bundle {
+            version: BUNDLE_VERSION,
+            apps: vec![plain, scripted],
+        };
+        assert_eq!(bundle.apps_with_custom_code().len(), 1);
+
+        bundle.strip_custom_code();
+        assert!(bundle.apps_with_custom_code().is_empty());
+    }
+}
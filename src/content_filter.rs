@@ -0,0 +1,811 @@
+//! Request blocking with EasyList style filter lists.
+//!
+//! Only network rules are supported: `||host^` anchors, `|` anchors, `*` and `^`
+//! wildcards, `@@` exceptions and the `third-party`, `domain` and resource type
+//! options. `@@…$document` exceptions turn blocking off for the pages they match.
+//! Element hiding, regular expressions and rules with other options are skipped, a
+//! rule we don't understand should never block anything.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::create_dir_all,
+    path::PathBuf,
+};
+
+use crate::APP_ID;
+
+/// Lists downloaded by `update_lists`, saved under their file name.
+pub const DEFAULT_LISTS: [(&str, &str); 2] = [
+    ("easylist.txt", "https://easylist.to/easylist/easylist.txt"),
+    (
+        "easyprivacy.txt",
+        "https://easylist.to/easylist/easyprivacy.txt",
+    ),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceKind {
+    Document,
+    Subdocument,
+    Stylesheet,
+    Script,
+    Image,
+    Font,
+    Media,
+    XmlHttpRequest,
+    Other,
+}
+
+impl ResourceKind {
+    fn from_option(option: &str) -> Option<Self> {
+        Some(match option {
+            "document" => ResourceKind::Document,
+            "subdocument" => ResourceKind::Subdocument,
+            "stylesheet" => ResourceKind::Stylesheet,
+            "script" => ResourceKind::Script,
+            "image" => ResourceKind::Image,
+            "font" => ResourceKind::Font,
+            "media" => ResourceKind::Media,
+            "xmlhttprequest" => ResourceKind::XmlHttpRequest,
+            "other" => ResourceKind::Other,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    /// Pattern after a `||` anchor, matched from the start of the host or one of its labels
+    host_anchored: bool,
+    start_anchored: bool,
+    end_anchored: bool,
+    pattern: String,
+    /// Longest literal part, a cheap pre-check before matching the pattern
+    literal: String,
+    exception: bool,
+    third_party: Option<bool>,
+    domains: Vec<String>,
+    excluded_domains: Vec<String>,
+    kinds: Vec<ResourceKind>,
+    excluded_kinds: Vec<ResourceKind>,
+}
+
+impl Rule {
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+
+        // comments, headers, element hiding and regular expressions
+        if line.is_empty()
+            || line.starts_with('!')
+            || line.starts_with('[')
+            || line.contains("##")
+            || line.contains("#@#")
+            || line.contains("#?#")
+            || line.contains("#$#")
+            || (line.starts_with('/') && line.ends_with('/'))
+        {
+            return None;
+        }
+
+        let (exception, line) = match line.strip_prefix("@@") {
+            Some(line) => (true, line),
+            None => (false, line),
+        };
+
+        let (pattern, options) = match line.rsplit_once('$') {
+            Some((pattern, options)) if !options.contains('/') => (pattern, Some(options)),
+            _ => (line, None),
+        };
+
+        let mut rule = Rule {
+            host_anchored: false,
+            start_anchored: false,
+            end_anchored: false,
+            pattern: String::new(),
+            literal: String::new(),
+            exception,
+            third_party: None,
+            domains: Vec::new(),
+            excluded_domains: Vec::new(),
+            kinds: Vec::new(),
+            excluded_kinds: Vec::new(),
+        };
+
+        for option in options.iter().flat_map(|options| options.split(',')) {
+            let (negated, name) = match option.strip_prefix('~') {
+                Some(name) => (true, name),
+                None => (false, option),
+            };
+
+            if let Some(domains) = name.strip_prefix("domain=") {
+                for domain in domains.split('|') {
+                    match domain.strip_prefix('~') {
+                        Some(domain) => rule.excluded_domains.push(domain.to_lowercase()),
+                        None => rule.domains.push(domain.to_lowercase()),
+                    }
+                }
+                continue;
+            }
+
+            match name {
+                "third-party" | "3p" => rule.third_party = Some(!negated),
+                "first-party" | "1p" => rule.third_party = Some(negated),
+                "match-case" | "important" => {}
+                _ => match ResourceKind::from_option(name) {
+                    Some(kind) if negated => rule.excluded_kinds.push(kind),
+                    Some(kind) => rule.kinds.push(kind),
+                    None => return None,
+                },
+            }
+        }
+
+        let mut pattern = pattern.to_lowercase();
+
+        if let Some(rest) = pattern.strip_prefix("||") {
+            rule.host_anchored = true;
+            pattern = rest.to_string();
+        } else if let Some(rest) = pattern.strip_prefix('|') {
+            rule.start_anchored = true;
+            pattern = rest.to_string();
+        }
+
+        if let Some(rest) = pattern.strip_suffix('|') {
+            rule.end_anchored = true;
+            pattern = rest.to_string();
+        }
+
+        let pattern = pattern.trim_matches('*').to_string();
+
+        // a rule matching everything is a mistake in the list
+        if pattern.is_empty() && !rule.exception {
+            return None;
+        }
+
+        rule.literal = pattern
+            .split(['*', '^'])
+            .max_by_key(|part| part.len())
+            .unwrap_or_default()
+            .to_string();
+        rule.pattern = pattern;
+
+        Some(rule)
+    }
+
+    /// Leading host name of a `||` rule, used to index it.
+    fn host_key(&self) -> Option<&str> {
+        if !self.host_anchored {
+            return None;
+        }
+
+        let end = self
+            .pattern
+            .find(['^', '/', '*', ':', '|'])
+            .unwrap_or(self.pattern.len());
+
+        Some(&self.pattern[..end]).filter(|host| !host.is_empty())
+    }
+
+    /// Longest run of token characters which is a whole token of every URL the rule
+    /// matches, used to index rules without a host.
+    fn token(&self) -> Option<&str> {
+        let pattern = self.pattern.as_bytes();
+        let mut best: Option<(usize, usize)> = None;
+        let mut start = 0;
+
+        for end in 0..=pattern.len() {
+            if end < pattern.len() && is_token_char(pattern[end]) {
+                continue;
+            }
+
+            // next to a wildcard or an open end the URL may continue the token
+            let bounded_start = if start == 0 {
+                self.host_anchored || self.start_anchored
+            } else {
+                pattern[start - 1] != b'*'
+            };
+            let bounded_end = if end == pattern.len() {
+                self.end_anchored
+            } else {
+                pattern[end] != b'*'
+            };
+
+            if end > start
+                && bounded_start
+                && bounded_end
+                && best.is_none_or(|(best_start, best_end)| end - start > best_end - best_start)
+            {
+                best = Some((start, end));
+            }
+
+            start = end + 1;
+        }
+
+        best.map(|(start, end)| &self.pattern[start..end])
+    }
+
+    fn matches(&self, request: &FilterRequest) -> bool {
+        if !request.url.contains(&self.literal) {
+            return false;
+        }
+
+        if !self.kinds.is_empty() && !self.kinds.contains(&request.kind) {
+            return false;
+        }
+
+        if self.excluded_kinds.contains(&request.kind) {
+            return false;
+        }
+
+        if self
+            .third_party
+            .is_some_and(|third_party| third_party != request.is_third_party())
+        {
+            return false;
+        }
+
+        let source_host = request.source_host.as_deref().unwrap_or_default();
+
+        if !self.domains.is_empty()
+            && !self
+                .domains
+                .iter()
+                .any(|domain| is_subdomain(source_host, domain))
+        {
+            return false;
+        }
+
+        if self
+            .excluded_domains
+            .iter()
+            .any(|domain| is_subdomain(source_host, domain))
+        {
+            return false;
+        }
+
+        self.matches_url(&request.url, request.host_start, &request.host)
+    }
+
+    fn matches_url(&self, url: &str, host_start: usize, host: &str) -> bool {
+        let pattern = self.pattern.as_bytes();
+        let url = url.as_bytes();
+
+        if self.host_anchored {
+            // the host itself and every subdomain boundary within it
+            let mut starts = std::iter::once(host_start)
+                .chain(host.match_indices('.').map(|(idx, _)| host_start + idx + 1));
+
+            return starts
+                .any(|start| glob_match(pattern, &url[start..], false, self.end_anchored));
+        }
+
+        glob_match(pattern, url, !self.start_anchored, self.end_anchored)
+    }
+}
+
+/// Characters URLs are split into tokens by, every other one separates tokens.
+fn is_token_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'%'
+}
+
+/// Characters which don't separate parts of an URL, `^` matches anything else or the end.
+fn is_separator(c: u8) -> bool {
+    !(c.is_ascii_alphanumeric() || matches!(c, b'_' | b'-' | b'.' | b'%'))
+}
+
+/// Matches `pattern` at the start of `text`, anywhere in it when `floating` and to
+/// its end when `anchored`. Only the last `*` is ever retried, which keeps matching
+/// linear in the length of the text for every wildcard.
+fn glob_match(pattern: &[u8], text: &[u8], floating: bool, anchored: bool) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // pattern position after the last `*` and the text position it's tried at
+    let mut retry = floating.then_some((0, 0));
+
+    loop {
+        if p == pattern.len() {
+            if !anchored || t == text.len() {
+                return true;
+            }
+        } else {
+            match (pattern[p], text.get(t)) {
+                (b'*', _) => {
+                    p += 1;
+                    retry = Some((p, t));
+                    continue;
+                }
+                // `^` also matches the end of the text
+                (b'^', None) => {
+                    p += 1;
+                    continue;
+                }
+                (b'^', Some(c)) if is_separator(*c) => {
+                    p += 1;
+                    t += 1;
+                    continue;
+                }
+                (c, Some(tc)) if c != b'^' && c == *tc => {
+                    p += 1;
+                    t += 1;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        match retry {
+            Some((retry_p, retry_t)) if retry_t < text.len() => {
+                retry = Some((retry_p, retry_t + 1));
+                p = retry_p;
+                t = retry_t + 1;
+            }
+            _ => return false,
+        }
+    }
+}
+
+fn is_subdomain(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// Registrable part of a host name, an approximation without the public suffix list.
+fn base_domain(host: &str) -> &str {
+    let labels = host.split('.').collect::<Vec<&str>>();
+
+    if labels.len() <= 2 {
+        return host;
+    }
+
+    // suffixes like `co.uk` or `com.au`
+    let second_level = labels[labels.len() - 2];
+    let count = if labels[labels.len() - 1].len() == 2 && second_level.len() <= 3 {
+        3
+    } else {
+        2
+    };
+
+    let skip = labels[..labels.len() - count.min(labels.len())]
+        .iter()
+        .map(|label| label.len() + 1)
+        .sum::<usize>();
+
+    &host[skip..]
+}
+
+/// A request checked against the filters.
+#[derive(Debug, Clone)]
+pub struct FilterRequest {
+    url: String,
+    host: String,
+    host_start: usize,
+    source: Option<String>,
+    source_host: Option<String>,
+    kind: ResourceKind,
+}
+
+impl FilterRequest {
+    /// `source` is the page the request originates from.
+    pub fn new(url: &str, source: Option<&str>, kind: ResourceKind) -> Option<Self> {
+        let parsed = url::Url::parse(url).ok()?;
+
+        if !matches!(parsed.scheme(), "http" | "https" | "ws" | "wss") {
+            return None;
+        }
+
+        let host = parsed.host_str()?.to_lowercase();
+        let url = url.to_lowercase();
+        let host_start = url.find(&host)?;
+
+        let source_host = source
+            .and_then(|source| url::Url::parse(source).ok())
+            .and_then(|source| source.host_str().map(str::to_lowercase));
+
+        Some(Self {
+            url,
+            host,
+            host_start,
+            source: source.map(str::to_string),
+            source_host,
+            kind,
+        })
+    }
+
+    fn is_third_party(&self) -> bool {
+        self.source_host
+            .as_deref()
+            .is_some_and(|source_host| base_domain(source_host) != base_domain(&self.host))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct RuleSet {
+    by_host: HashMap<String, Vec<Rule>>,
+    by_token: HashMap<String, Vec<Rule>>,
+    /// Rules without a host or token, checked against every request
+    generic: Vec<Rule>,
+}
+
+impl RuleSet {
+    fn add(&mut self, rule: Rule) {
+        if let Some(host) = rule.host_key() {
+            self.by_host.entry(host.to_string()).or_default().push(rule);
+        } else if let Some(token) = rule.token() {
+            self.by_token
+                .entry(token.to_string())
+                .or_default()
+                .push(rule);
+        } else {
+            self.generic.push(rule);
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.by_host.is_empty() && self.by_token.is_empty() && self.generic.is_empty()
+    }
+
+    fn matches(&self, request: &FilterRequest) -> bool {
+        let host = request.host.as_str();
+        let suffixes =
+            std::iter::once(host).chain(host.match_indices('.').map(|(idx, _)| &host[idx + 1..]));
+
+        let tokens = request
+            .url
+            .split(|c: char| !c.is_ascii() || !is_token_char(c as u8))
+            .filter(|token| !token.is_empty())
+            .collect::<HashSet<&str>>();
+
+        suffixes
+            .filter_map(|suffix| self.by_host.get(suffix))
+            .chain(
+                tokens
+                    .into_iter()
+                    .filter_map(|token| self.by_token.get(token)),
+            )
+            .flatten()
+            .chain(self.generic.iter())
+            .any(|rule| rule.matches(request))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FilterSet {
+    blocking: RuleSet,
+    exceptions: RuleSet,
+    /// `$document` exceptions, pages they match aren't filtered at all
+    documents: RuleSet,
+}
+
+pub fn filters_dir() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join(APP_ID).join("filters"))
+}
+
+impl FilterSet {
+    /// Loads every `.txt` list in the filters directory.
+    pub fn load() -> Self {
+        let mut filters = FilterSet::default();
+
+        let Some(entries) = filters_dir().and_then(|dir| std::fs::read_dir(dir).ok()) else {
+            return filters;
+        };
+
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+
+            if path.extension().is_some_and(|extension| extension == "txt") {
+                match std::fs::read_to_string(&path) {
+                    Ok(content) => filters.add_list(&content),
+                    Err(e) => eprintln!("Failed to read filter list {}: {}", path.display(), e),
+                }
+            }
+        }
+
+        filters
+    }
+
+    pub fn add_list(&mut self, content: &str) {
+        for rule in content.lines().filter_map(Rule::parse) {
+            if rule.exception {
+                if rule.kinds.contains(&ResourceKind::Document) {
+                    self.documents.add(rule.clone());
+                }
+                self.exceptions.add(rule);
+            } else {
+                self.blocking.add(rule);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocking.is_empty()
+    }
+
+    /// Whether a `$document` exception turns filtering off for the page at `url`.
+    pub fn allows_page(&self, url: &str) -> bool {
+        FilterRequest::new(url, None, ResourceKind::Document)
+            .is_some_and(|page| self.documents.matches(&page))
+    }
+
+    pub fn should_block(&self, request: &FilterRequest) -> bool {
+        let page_allowed = request
+            .source
+            .as_deref()
+            .is_some_and(|source| self.allows_page(source));
+
+        !page_allowed && self.blocking.matches(request) && !self.exceptions.matches(request)
+    }
+}
+
+/// Downloads the default filter lists, replacing older copies. Returns the number of lists saved.
+pub fn update_lists() -> anyhow::Result<usize> {
+    let Some(dir) = filters_dir() else {
+        anyhow::bail!("cannot locate filters directory");
+    };
+
+    create_dir_all(&dir)?;
+
+    let mut saved = 0;
+
+    for (name, url) in DEFAULT_LISTS {
+        let content = match crate::http::get_text(url) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Failed to download {}: {}", url, e);
+                continue;
+            }
+        };
+
+        std::fs::write(dir.join(name), content)?;
+        saved += 1;
+    }
+
+    if saved == 0 {
+        anyhow::bail!("no filter list could be downloaded");
+    }
+
+    Ok(saved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocks(list: &str, url: &str, source: &str, kind: ResourceKind) -> bool {
+        let mut filters = FilterSet::default();
+        filters.add_list(list);
+
+        let request = FilterRequest::new(url, Some(source), kind).expect("valid request");
+        filters.should_block(&request)
+    }
+
+    #[test]
+    fn host_anchor() {
+        let list = "||ads.example.com^";
+        let page = "https://news.test/";
+
+        assert!(blocks(
+            list,
+            "https://ads.example.com/banner.png",
+            page,
+            ResourceKind::Image
+        ));
+        assert!(blocks(
+            list,
+            "https://cdn.ads.example.com/a.js",
+            page,
+            ResourceKind::Script
+        ));
+        assert!(!blocks(
+            list,
+            "https://badads.example.com/a.js",
+            page,
+            ResourceKind::Script
+        ));
+        assert!(!blocks(
+            list,
+            "https://example.com/ads.example.com",
+            page,
+            ResourceKind::Script
+        ));
+    }
+
+    #[test]
+    fn separator() {
+        let list = "||example.com/track^";
+        let page = "https://news.test/";
+
+        assert!(blocks(
+            list,
+            "https://example.com/track?id=1",
+            page,
+            ResourceKind::Image
+        ));
+        assert!(blocks(
+            list,
+            "https://example.com/track",
+            page,
+            ResourceKind::Image
+        ));
+        assert!(!blocks(
+            list,
+            "https://example.com/tracking",
+            page,
+            ResourceKind::Image
+        ));
+    }
+
+    #[test]
+    fn wildcard() {
+        let list = "/banner/*/ad_";
+        let page = "https://news.test/";
+
+        assert!(blocks(
+            list,
+            "https://cdn.test/banner/300x250/ad_1.gif",
+            page,
+            ResourceKind::Image
+        ));
+        assert!(!blocks(
+            list,
+            "https://cdn.test/banner/ad_1.gif",
+            page,
+            ResourceKind::Image
+        ));
+    }
+
+    #[test]
+    fn exception() {
+        let list = "||example.com^\n@@||example.com/allowed/";
+        let page = "https://news.test/";
+
+        assert!(blocks(
+            list,
+            "https://example.com/ad.js",
+            page,
+            ResourceKind::Script
+        ));
+        assert!(!blocks(
+            list,
+            "https://example.com/allowed/app.js",
+            page,
+            ResourceKind::Script
+        ));
+    }
+
+    #[test]
+    fn domain_option() {
+        let list = "||tracker.test^$domain=news.test|~sport.news.test";
+
+        let url = "https://tracker.test/pixel.gif";
+        assert!(blocks(list, url, "https://news.test/", ResourceKind::Image));
+        assert!(blocks(
+            list,
+            url,
+            "https://www.news.test/",
+            ResourceKind::Image
+        ));
+        assert!(!blocks(
+            list,
+            url,
+            "https://sport.news.test/",
+            ResourceKind::Image
+        ));
+        assert!(!blocks(
+            list,
+            url,
+            "https://blog.test/",
+            ResourceKind::Image
+        ));
+    }
+
+    #[test]
+    fn third_party_option() {
+        let list = "||widgets.test^$third-party";
+
+        let url = "https://widgets.test/embed.js";
+        assert!(blocks(
+            list,
+            url,
+            "https://news.test/",
+            ResourceKind::Script
+        ));
+        assert!(!blocks(
+            list,
+            url,
+            "https://www.widgets.test/",
+            ResourceKind::Script
+        ));
+    }
+
+    #[test]
+    fn document_exception() {
+        let list = "||tracker.test^\n@@||intranet.test^$document";
+        let url = "https://tracker.test/pixel.gif";
+
+        assert!(blocks(list, url, "https://news.test/", ResourceKind::Image));
+        assert!(!blocks(
+            list,
+            url,
+            "https://wiki.intranet.test/start",
+            ResourceKind::Image
+        ));
+
+        let mut filters = FilterSet::default();
+        filters.add_list(list);
+        assert!(filters.allows_page("https://intranet.test/"));
+        assert!(!filters.allows_page("https://news.test/"));
+    }
+
+    #[test]
+    fn indexes_rules_by_token() {
+        let rules = [
+            "/banner/*/ad_",
+            "-ad-",
+            "ad",
+            "||example.com^",
+            "|https://ads.",
+        ];
+        let mut filters = FilterSet::default();
+        filters.add_list(&rules.join("\n"));
+
+        assert_eq!(filters.blocking.by_host.len(), 1);
+        assert_eq!(filters.blocking.by_token.len(), 3);
+        assert!(filters.blocking.by_token.contains_key("banner"));
+        assert!(filters.blocking.by_token.contains_key("https"));
+        // a token the URL may continue can't be looked up
+        assert_eq!(filters.blocking.generic.len(), 1);
+
+        let page = "https://news.test/";
+        assert!(blocks(
+            "-ad-",
+            "https://cdn.test/img-ad-1.png",
+            page,
+            ResourceKind::Image
+        ));
+        assert!(blocks(
+            "ad",
+            "https://cdn.test/load.js",
+            page,
+            ResourceKind::Script
+        ));
+    }
+
+    #[test]
+    fn wildcards_match_in_linear_time() {
+        let list = "a*a*a*a*a*a*a*a*b";
+        let url = format!("https://cdn.test/{}", "a".repeat(4000));
+
+        assert!(!blocks(
+            list,
+            &url,
+            "https://news.test/",
+            ResourceKind::Image
+        ));
+        assert!(blocks(
+            list,
+            &format!("{}b", url),
+            "https://news.test/",
+            ResourceKind::Image
+        ));
+    }
+
+    #[test]
+    fn separator_matches_the_end() {
+        assert!(glob_match(b"track^", b"track", false, true));
+        assert!(glob_match(b"track^*^", b"track?id=1", false, false));
+        assert!(!glob_match(b"track^", b"tracking", false, false));
+        assert!(glob_match(b"a*b", b"xxaxxb", true, true));
+    }
+
+    #[test]
+    fn skips_unsupported_rules() {
+        let list = "example.com##.ad\n/ads?[0-9]/\n||example.com^$popup";
+
+        assert!(!blocks(
+            list,
+            "https://example.com/ads1.js",
+            "https://example.com/",
+            ResourceKind::Script
+        ));
+    }
+}
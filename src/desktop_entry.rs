@@ -0,0 +1,326 @@
+use std::fmt::Display;
+
+/// Builder for freedesktop.org `.desktop` files.
+///
+/// Values are escaped following the Desktop Entry Specification, so user input
+/// like app names can't inject additional keys or groups.
+#[derive(Debug, Clone)]
+pub struct DesktopEntry {
+    groups: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl Default for DesktopEntry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DesktopEntry {
+    pub fn new() -> Self {
+        Self {
+            groups: vec![(String::from("Desktop Entry"), Vec::new())],
+        }
+    }
+
+    /// Starts a new group, following keys are added to it.
+    pub fn group(mut self, name: &str) -> Self {
+        self.groups.push((name.to_string(), Vec::new()));
+        self
+    }
+
+    pub fn string(self, key: &str, value: &str) -> Self {
+        self.raw(key, escape_value(value))
+    }
+
+    pub fn boolean(self, key: &str, value: bool) -> Self {
+        self.raw(key, value.to_string())
+    }
+
+    pub fn list<S: AsRef<str>>(self, key: &str, values: &[S]) -> Self {
+        let value = values
+            .iter()
+            .map(|value| format!("{};", escape_value(value.as_ref()).replace(';', "\\;")))
+            .collect::<String>();
+
+        self.raw(key, value)
+    }
+
+    /// Adds an `Exec` key. Arguments are quoted, field codes like `%u` are kept as they are.
+    pub fn exec<S: AsRef<str>>(self, args: &[S]) -> Self {
+        let line = args
+            .iter()
+            .map(|arg| {
+                let arg = arg.as_ref();
+
+                if is_field_code(arg) {
+                    arg.to_string()
+                } else {
+                    quote_exec_arg(arg)
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        self.string("Exec", &line)
+    }
+
+    fn raw(mut self, key: &str, value: String) -> Self {
+        if let Some((_, entries)) = self.groups.last_mut() {
+            entries.push((key.to_string(), value));
+        }
+        self
+    }
+}
+
+impl Display for DesktopEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (idx, (group, entries)) in self.groups.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+
+            writeln!(f, "[{}]", group)?;
+
+            for (key, value) in entries {
+                writeln!(f, "{}={}", key, value)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Escapes a value of type `string` or `localestring`.
+pub fn escape_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for (idx, c) in value.chars().enumerate() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            ' ' if idx == 0 => escaped.push_str("\\s"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Reverses `escape_value`.
+pub fn unescape_value(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('s') => unescaped.push(' '),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+/// Command line of an unescaped `Exec` value opening `url`. The url is appended when
+/// the value has no field code for it.
+pub fn expand_exec(exec: &str, url: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut arg = String::new();
+    let mut in_arg = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_arg = true;
+
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => arg.extend(chars.next()),
+                        c => arg.push(c),
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut arg));
+                    in_arg = false;
+                }
+            }
+            c => {
+                in_arg = true;
+                arg.push(c);
+            }
+        }
+    }
+
+    if in_arg {
+        args.push(arg);
+    }
+
+    let mut expanded = Vec::new();
+    let mut url_passed = false;
+
+    for arg in args {
+        match arg.as_str() {
+            "%f" | "%F" | "%u" | "%U" => {
+                expanded.push(url.to_string());
+                url_passed = true;
+            }
+            // the remaining field codes, deprecated ones included, expand to nothing here
+            arg if arg.len() == 2 && arg.starts_with('%') && arg != "%%" => {}
+            _ => expanded.push(arg.replace("%%", "%")),
+        }
+    }
+
+    if !url_passed {
+        expanded.push(url.to_string());
+    }
+
+    expanded
+}
+
+/// Value of `key` in the `[Desktop Entry]` group of a desktop file.
+pub fn read_key(content: &str, key: &str) -> Option<String> {
+    let mut in_main_group = false;
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if line.starts_with('[') {
+            in_main_group = line == "[Desktop Entry]";
+            continue;
+        }
+
+        if !in_main_group {
+            continue;
+        }
+
+        let value = line
+            .split_once('=')
+            .filter(|(name, _)| name.trim() == key)
+            .map(|(_, value)| value);
+
+        if let Some(value) = value {
+            return Some(unescape_value(value.trim()));
+        }
+    }
+
+    None
+}
+
+/// Quotes a single argument of the `Exec` key.
+pub fn quote_exec_arg(arg: &str) -> String {
+    let reserved = [
+        ' ', '\t', '\n', '"', '\'', '\\', '>', '<', '~', '|', '&', ';', '$', '*', '?', '#', '(',
+        ')', '`',
+    ];
+
+    let arg = arg.replace('%', "%%");
+
+    if !arg.is_empty() && !arg.contains(reserved) {
+        return arg;
+    }
+
+    let mut quoted = String::from("\"");
+
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+fn is_field_code(arg: &str) -> bool {
+    matches!(arg, "%f" | "%F" | "%u" | "%U" | "%i" | "%c" | "%k")
+}
+
+/// Turns an action name into a valid `[Desktop Action <id>]` identifier.
+pub fn action_id(idx: usize, name: &str) -> String {
+    let name = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>();
+
+    format!("{}{}", name, idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_values() {
+        assert_eq!(escape_value("Mail"), "Mail");
+        assert_eq!(escape_value(" Mail"), "\\sMail");
+        assert_eq!(escape_value("a b"), "a b");
+        assert_eq!(escape_value("a\\b"), "a\\\\b");
+        assert_eq!(escape_value("a\nb\tc\rd"), "a\\nb\\tc\\rd");
+    }
+
+    #[test]
+    fn values_cannot_add_keys() {
+        let entry = DesktopEntry::new()
+            .string("Name", "Mail\nExec=evil\n[Desktop Action evil]")
+            .to_string();
+
+        assert_eq!(entry.lines().count(), 2);
+        assert_eq!(read_key(&entry, "Exec"), None);
+        assert_eq!(
+            read_key(&entry, "Name").as_deref(),
+            Some("Mail\nExec=evil\n[Desktop Action evil]")
+        );
+    }
+
+    #[test]
+    fn quotes_exec_args() {
+        assert_eq!(quote_exec_arg("--app-id"), "--app-id");
+        assert_eq!(quote_exec_arg(""), "\"\"");
+        assert_eq!(quote_exec_arg("my app"), "\"my app\"");
+        assert_eq!(quote_exec_arg("100%"), "100%%");
+        assert_eq!(quote_exec_arg("$HOME"), "\"\\$HOME\"");
+        assert_eq!(quote_exec_arg("a\"b`c\\d"), "\"a\\\"b\\`c\\\\d\"");
+    }
+
+    #[test]
+    fn exec_round_trip() {
+        let args = ["/opt/web apps/webview", "--id", "Mail$1", "100%", "%u"];
+        let entry = DesktopEntry::new().exec(&args).to_string();
+
+        let exec = read_key(&entry, "Exec").expect("Exec key");
+        assert_eq!(
+            expand_exec(&exec, "https://example.com"),
+            [
+                "/opt/web apps/webview",
+                "--id",
+                "Mail$1",
+                "100%",
+                "https://example.com"
+            ]
+        );
+    }
+
+    #[test]
+    fn appends_url_without_field_code() {
+        assert_eq!(
+            expand_exec("firefox --new-window %i", "https://example.com"),
+            ["firefox", "--new-window", "https://example.com"]
+        );
+    }
+}
@@ -0,0 +1,74 @@
+//! Devices a web app can pretend to be when simulating a mobile device.
+
+use crate::{WindowSize, user_agent};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Device {
+    pub name: &'static str,
+    /// Viewport in CSS pixels
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+    pub user_agent: &'static str,
+}
+
+const SAFARI_IPAD: &str = "Mozilla/5.0 (iPad; CPU OS 18_5 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/18.5 Mobile/15E148 Safari/604.1";
+
+pub const PRESETS: [Device; 6] = [
+    Device {
+        name: "Pixel 7",
+        width: 412,
+        height: 915,
+        scale_factor: 2.625,
+        user_agent: user_agent::MOBILE,
+    },
+    Device {
+        name: "Galaxy S23",
+        width: 360,
+        height: 780,
+        scale_factor: 3.0,
+        user_agent: user_agent::MOBILE,
+    },
+    Device {
+        name: "iPhone 15",
+        width: 393,
+        height: 852,
+        scale_factor: 3.0,
+        user_agent: user_agent::SAFARI_IPHONE,
+    },
+    Device {
+        name: "iPhone SE",
+        width: 375,
+        height: 667,
+        scale_factor: 2.0,
+        user_agent: user_agent::SAFARI_IPHONE,
+    },
+    Device {
+        name: "iPad Mini",
+        width: 768,
+        height: 1024,
+        scale_factor: 2.0,
+        user_agent: SAFARI_IPAD,
+    },
+    Device {
+        name: "Galaxy Tab S9",
+        width: 800,
+        height: 1280,
+        scale_factor: 2.0,
+        user_agent: user_agent::MOBILE,
+    },
+];
+
+impl Device {
+    /// The preset called `name`, the first one when there is none.
+    pub fn find(name: Option<&str>) -> &'static Device {
+        PRESETS
+            .iter()
+            .find(|device| Some(device.name) == name)
+            .unwrap_or(&PRESETS[0])
+    }
+
+    pub fn window_size(&self) -> WindowSize {
+        WindowSize(self.width, self.height)
+    }
+}
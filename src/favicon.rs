@@ -0,0 +1,169 @@
+//! Icons a site offers for itself: favicons, `apple-touch-icon` and the icons of
+//! its web app manifest.
+
+use cosmic::iced_winit::graphics::image::image_rs::{ImageFormat, ImageReader};
+use std::{
+    fs::create_dir_all,
+    path::{Path, PathBuf},
+};
+use url::Url;
+
+use crate::{APP_ID, http, launcher::WebappIcon, manifest};
+
+const ICON_RELS: [&str; 3] = ["icon", "apple-touch-icon", "apple-touch-icon-precomposed"];
+
+/// A downloaded icon. Raster icons are stored as PNG whatever the site served.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IconCandidate {
+    pub url: String,
+    pub path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    pub scalable: bool,
+}
+
+/// Where candidates wait until the user picks one.
+fn candidates_dir() -> Option<PathBuf> {
+    Some(dirs::cache_dir()?.join(APP_ID).join("favicons"))
+}
+
+/// Downloads the icon at `icon_url` into `dir`.
+pub fn download_icon(icon_url: &str, dir: &Path) -> anyhow::Result<IconCandidate> {
+    let (bytes, content_type) = http::get_bytes(icon_url)?;
+
+    let url = Url::parse(icon_url)?;
+    let stem = url.host_str().unwrap_or("icon").replace('.', "_");
+    let digest = bytes.iter().fold(0u32, |hash, byte| {
+        hash.wrapping_mul(31).wrapping_add(*byte as u32)
+    });
+
+    let scalable = content_type.as_deref() == Some("image/svg+xml") || url.path().ends_with(".svg");
+
+    let (path, buffer, width, height) = if scalable {
+        let tree = usvg::Tree::from_data(&bytes, &usvg::Options::default())?;
+        let size = tree.size();
+
+        (
+            dir.join(format!("{stem}_{digest:08x}.svg")),
+            bytes,
+            size.width() as u32,
+            size.height() as u32,
+        )
+    } else {
+        let image = ImageReader::new(std::io::Cursor::new(&bytes))
+            .with_guessed_format()?
+            .decode()?;
+
+        let mut png = Vec::new();
+        image.write_to(&mut std::io::Cursor::new(&mut png), ImageFormat::Png)?;
+
+        (
+            dir.join(format!("{stem}_{digest:08x}.png")),
+            png,
+            image.width(),
+            image.height(),
+        )
+    };
+
+    create_dir_all(dir)?;
+    std::fs::write(&path, &buffer)?;
+
+    Ok(IconCandidate {
+        url: icon_url.to_string(),
+        path,
+        width,
+        height,
+        scalable,
+    })
+}
+
+/// URLs of every icon the site at `page_url` declares, plus the conventional locations.
+pub fn icon_urls(page_url: &str) -> anyhow::Result<Vec<String>> {
+    let page_url = Url::parse(page_url)?;
+    let mut urls = Vec::new();
+
+    // the page itself may fail to load while `/favicon.ico` still works
+    match http::get_text(page_url.as_str()) {
+        Ok(html) => {
+            let links = manifest::html_links(&html);
+
+            urls.extend(
+                links
+                    .iter()
+                    .filter(|link| {
+                        link.rel
+                            .split_whitespace()
+                            .any(|rel| ICON_RELS.contains(&rel))
+                    })
+                    .filter_map(|link| page_url.join(&link.href).ok())
+                    .map(String::from),
+            );
+
+            if let Some((manifest, manifest_url)) = manifest::fetch_manifest(&page_url, &links) {
+                urls.extend(manifest.icon_urls(&manifest_url));
+            }
+        }
+        Err(e) => eprintln!("Failed to load {}: {}", page_url, e),
+    }
+
+    for fallback in ["/favicon.ico", "/apple-touch-icon.png"] {
+        if let Ok(url) = page_url.join(fallback) {
+            urls.push(String::from(url));
+        }
+    }
+
+    let mut unique = Vec::new();
+    for url in urls {
+        if !unique.contains(&url) {
+            unique.push(url);
+        }
+    }
+
+    Ok(unique)
+}
+
+/// Downloads every icon of the site, largest first. Icons which fail to load are left out.
+pub fn fetch_candidates(page_url: &str) -> anyhow::Result<Vec<IconCandidate>> {
+    let Some(dir) = candidates_dir() else {
+        anyhow::bail!("cannot locate cache directory");
+    };
+
+    let mut candidates = Vec::new();
+
+    for url in icon_urls(page_url)? {
+        match download_icon(&url, &dir) {
+            Ok(candidate) => {
+                if !candidates
+                    .iter()
+                    .any(|known: &IconCandidate| known.path == candidate.path)
+                {
+                    candidates.push(candidate);
+                }
+            }
+            Err(e) => eprintln!("Failed to load icon {}: {}", url, e),
+        }
+    }
+
+    candidates.sort_by_key(|candidate| {
+        std::cmp::Reverse((candidate.scalable, candidate.width.max(candidate.height)))
+    });
+
+    Ok(candidates)
+}
+
+/// Copies the chosen candidate into the icons folder, so launchers can use it.
+pub fn keep(candidate: &IconCandidate) -> anyhow::Result<WebappIcon> {
+    let Some(location) = crate::icons_location() else {
+        anyhow::bail!("cannot locate icons directory");
+    };
+
+    let Some(file_name) = candidate.path.file_name() else {
+        anyhow::bail!("invalid icon path {}", candidate.path.display());
+    };
+
+    let path = location.join(file_name);
+    let buffer = std::fs::read(&candidate.path)?;
+    std::fs::write(&path, &buffer)?;
+
+    Ok(WebappIcon { path, buffer })
+}
@@ -0,0 +1,53 @@
+//! Blocking HTTP requests for the few things fetched from the web.
+
+use std::time::Duration;
+
+use crate::user_agent;
+
+// filter lists are a few megabytes, nothing else comes close
+const MAX_BODY_SIZE: u64 = 32 * 1024 * 1024;
+
+fn agent() -> ureq::Agent {
+    ureq::Agent::config_builder()
+        .timeout_global(Some(Duration::from_secs(30)))
+        .user_agent(user_agent::fallback())
+        .build()
+        .into()
+}
+
+pub fn get_text(url: &str) -> anyhow::Result<String> {
+    let text = agent()
+        .get(url)
+        .call()?
+        .body_mut()
+        .with_config()
+        .limit(MAX_BODY_SIZE)
+        .read_to_string()?;
+
+    Ok(text)
+}
+
+/// Body and content type of `url`.
+pub fn get_bytes(url: &str) -> anyhow::Result<(Vec<u8>, Option<String>)> {
+    let mut response = agent().get(url).call()?;
+
+    let content_type = response
+        .headers()
+        .get("content-type")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value
+                .split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        });
+    let bytes = response
+        .body_mut()
+        .with_config()
+        .limit(MAX_BODY_SIZE)
+        .read_to_vec()?;
+
+    Ok((bytes, content_type))
+}
@@ -1,3 +1,4 @@
+use anyhow::Context as _;
 use ashpd::desktop::{
     Icon,
     dynamic_launcher::{
@@ -5,10 +6,17 @@ use ashpd::desktop::{
     },
 };
 use serde::{Deserialize, Serialize};
-use std::{io::Read as _, path::PathBuf};
+use std::{
+    io::Read as _,
+    path::{Path, PathBuf},
+};
 use tokio::fs::remove_file;
 
-use crate::{APP_ID, handle_icon};
+use crate::{
+    APP_ID,
+    desktop_entry::{self, DesktopEntry},
+    handle_icon,
+};
 
 pub fn webapplauncher_is_valid(name: &str, url: &Option<String>) -> bool {
     if let Some(url) = url {
@@ -20,28 +28,126 @@ pub fn webapplauncher_is_valid(name: &str, url: &Option<String>) -> bool {
     false
 }
 
+/// Version of the `database/*.ron` records written by this build.
+pub const DATABASE_VERSION: u32 = 1;
+
+/// Database record which could not be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidRecord {
+    pub path: PathBuf,
+    pub error: String,
+}
+
 pub fn installed_webapps() -> Vec<WebAppLauncher> {
+    let (webapps, invalid) = load_database();
+
+    for record in invalid {
+        tracing::warn!("skipping {}: {}", record.path.display(), record.error);
+    }
+
+    webapps
+}
+
+/// Installed web apps as their records are, without upgrading outdated ones. For short
+/// lived callers like the link dispatcher, which shouldn't write to the database.
+pub fn read_installed_webapps() -> Vec<WebAppLauncher> {
+    database_records()
+        .iter()
+        .filter_map(|path| read_record(path).ok())
+        .map(|(launcher, _)| launcher)
+        .collect()
+}
+
+/// Loads every database record, upgrading outdated ones in place.
+pub fn load_database() -> (Vec<WebAppLauncher>, Vec<InvalidRecord>) {
     let mut webapps = Vec::new();
+    let mut invalid = Vec::new();
 
-    if let Some(data_dir) = dirs::data_dir() {
-        if let Ok(entries) = std::fs::read_dir(data_dir.join(APP_ID).join("database")) {
-            for entry in entries {
-                if let Ok(entry) = entry {
-                    let file = std::fs::File::open(entry.path());
-                    let mut content = String::new();
-
-                    if let Ok(mut f) = file {
-                        f.read_to_string(&mut content).unwrap();
-                        if let Ok(launcher) = ron::from_str::<WebAppLauncher>(&content) {
-                            webapps.push(launcher);
-                        }
-                    }
-                }
-            }
+    for path in database_records() {
+        match load_record(&path) {
+            Ok(launcher) => webapps.push(launcher),
+            Err(e) => invalid.push(InvalidRecord {
+                path,
+                error: format!("{e:#}"),
+            }),
         }
     }
 
-    webapps
+    (webapps, invalid)
+}
+
+fn database_records() -> Vec<PathBuf> {
+    let Some(data_dir) = dirs::data_dir() else {
+        return Vec::new();
+    };
+
+    let Ok(entries) = std::fs::read_dir(data_dir.join(APP_ID).join("database")) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+        .collect()
+}
+
+fn read_record(path: &Path) -> anyhow::Result<(WebAppLauncher, u32)> {
+    let mut content = String::new();
+    std::fs::File::open(path)?.read_to_string(&mut content)?;
+
+    parse_record(&content)
+}
+
+fn load_record(path: &Path) -> anyhow::Result<WebAppLauncher> {
+    let (launcher, from) = read_record(path)?;
+
+    if from < DATABASE_VERSION {
+        tracing::info!(
+            "migrated {} from version {} to {}",
+            path.display(),
+            from,
+            DATABASE_VERSION
+        );
+        launcher.save()?;
+    }
+
+    Ok(launcher)
+}
+
+/// Just the version of a record, whatever else it holds.
+#[derive(Deserialize)]
+struct RecordVersion {
+    #[serde(default)]
+    version: u32,
+}
+
+/// Parses a record and upgrades it to `DATABASE_VERSION`, also returns the version it had.
+fn parse_record(content: &str) -> anyhow::Result<(WebAppLauncher, u32)> {
+    let version = ron::from_str::<RecordVersion>(content)?.version;
+
+    if version > DATABASE_VERSION {
+        anyhow::bail!(
+            "record version {} is newer than supported version {}",
+            version,
+            DATABASE_VERSION
+        );
+    }
+
+    // every field added since version 0 is optional, so older records parse as they are
+    let mut launcher = ron::from_str::<WebAppLauncher>(content)?;
+    migrate(&mut launcher, version);
+
+    Ok((launcher, version))
+}
+
+fn migrate(launcher: &mut WebAppLauncher, from: u32) {
+    // 0 -> 1: profiles could not be shared yet, every record used an isolated one
+    if from < 1 && launcher.browser.isolated.is_none() {
+        launcher.browser.isolated = Some(true);
+    }
+
+    launcher.version = DATABASE_VERSION;
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -62,17 +168,70 @@ pub fn webapp_icon_valid(icon: &WebappIcon) -> bool {
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct WebAppLauncher {
+    #[serde(default)]
+    pub version: u32,
     pub browser: crate::browser::Browser,
     pub name: String,
     pub icon: WebappIcon,
     pub category: crate::Category,
+    pub comment: Option<String>,
+    pub keywords: Option<Vec<String>>,
+    pub mime_types: Option<Vec<String>>,
+    pub startup_notify: Option<bool>,
+    pub actions: Option<Vec<DesktopAction>>,
+}
+
+/// Quick link to a sub-page, shown in the launcher's context menu.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DesktopAction {
+    pub name: String,
+    pub url: String,
 }
 
 impl WebAppLauncher {
-    pub async fn create(&self) -> anyhow::Result<bool> {
-        let mut desktop_entry = String::new();
+    pub fn new(
+        browser: crate::browser::Browser,
+        name: String,
+        icon: WebappIcon,
+        category: crate::Category,
+    ) -> Self {
+        Self {
+            version: DATABASE_VERSION,
+            browser,
+            name,
+            icon,
+            category,
+            comment: None,
+            keywords: None,
+            mime_types: None,
+            startup_notify: None,
+            actions: None,
+        }
+    }
+
+    pub fn from_appid(id: &str) -> Option<Self> {
+        installed_webapps()
+            .into_iter()
+            .find(|launcher| launcher.browser.app_id.as_ref() == id)
+    }
+
+    fn to_record(&self) -> ron::Result<String> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
 
-        let Some(exe) = self.browser.get_exec() else {
+    pub fn save(&self) -> anyhow::Result<()> {
+        let Some(location) = crate::database_path(&format!("{}.ron", self.browser.app_id.as_ref()))
+        else {
+            anyhow::bail!("cannot locate database directory");
+        };
+
+        std::fs::write(location, self.to_record()?)?;
+
+        Ok(())
+    }
+
+    pub async fn create(&self) -> anyhow::Result<bool> {
+        let Some(exec) = self.browser.get_exec() else {
             return Ok(false);
         };
 
@@ -81,18 +240,11 @@ impl WebAppLauncher {
             return Ok(false);
         };
 
-        desktop_entry.push_str("[Desktop Entry]\n");
-        desktop_entry.push_str("Version=1.0\n");
-        desktop_entry.push_str("Type=Application\n");
-        desktop_entry.push_str(&format!("Name={}\n", self.name));
-        desktop_entry.push_str(&format!("Comment=Quick WebApp\n",));
-        desktop_entry.push_str(&format!("Exec={}\n", exe));
-        desktop_entry.push_str(&format!("StartupWMClass={}\n", self.browser.app_id.id));
-        desktop_entry.push_str(&format!("Categories={}\n", self.category.as_ref()));
+        let desktop_entry = self.desktop_entry(exec).to_string();
 
         let proxy = DynamicLauncherProxy::new()
             .await
-            .expect("Failed to create DynamicLauncherProxy");
+            .context("dynamic launcher portal unavailable")?;
 
         let icon = Icon::Bytes(self.icon.buffer.clone());
 
@@ -101,9 +253,9 @@ impl WebAppLauncher {
         let response = proxy
             .prepare_install(None, &self.name, icon, prepare_opts)
             .await
-            .expect("Failed to prepare install")
+            .context("failed to prepare install")?
             .response()
-            .expect("Failed to get response");
+            .context("install request refused")?;
 
         let token = response.token();
 
@@ -117,15 +269,100 @@ impl WebAppLauncher {
                 InstallOptions::default(),
             )
             .await
-            .expect("installing");
+            .context("failed to install launcher")?;
+
+        Ok(true)
+    }
+
+    /// `MimeType` entries of the desktop entry. The webview opens urls only, not files,
+    /// so just the schemes of its protocol handlers are declared.
+    fn handled_mime_types(&self) -> Vec<String> {
+        let handled = self
+            .browser
+            .protocol_handlers
+            .iter()
+            .flatten()
+            // http(s) links reach web apps through the dispatcher, an app declaring them
+            // would show up as a browser
+            .filter_map(|handler| {
+                crate::url_handler::pattern_scheme(&format!("{}:", handler.protocol))
+                    .filter(|scheme| !matches!(scheme.as_str(), "http" | "https"))
+            })
+            .map(|scheme| format!("x-scheme-handler/{}", scheme))
+            .collect::<Vec<String>>();
+
+        let mut mime_types = Vec::new();
+        for mime_type in self.mime_types.iter().flatten().chain(&handled) {
+            if !handled.contains(mime_type) {
+                tracing::warn!(
+                    "not declaring {} for {}, it needs a protocol handler opening it",
+                    mime_type,
+                    self.name
+                );
+            } else if !mime_types.contains(mime_type) {
+                mime_types.push(mime_type.clone());
+            }
+        }
+
+        mime_types
+    }
+
+    pub fn desktop_entry(&self, exec: Vec<String>) -> DesktopEntry {
+        let mime_types = self.handled_mime_types();
+        let actions = self.actions.clone().unwrap_or_default();
+
+        let mut main_exec = exec.clone();
+        if !mime_types.is_empty() {
+            main_exec.push(String::from("%u"));
+        }
+
+        let mut entry = DesktopEntry::new()
+            .string("Version", "1.0")
+            .string("Type", "Application")
+            .string("Name", &self.name)
+            .string("Comment", self.comment.as_deref().unwrap_or("Quick WebApp"))
+            .exec(&main_exec)
+            .string("StartupWMClass", &self.browser.app_id.id)
+            .list("Categories", &[self.category.as_ref()])
+            .boolean("StartupNotify", self.startup_notify.unwrap_or_default());
+
+        if let Some(keywords) = &self.keywords {
+            if !keywords.is_empty() {
+                entry = entry.list("Keywords", keywords);
+            }
+        }
+
+        if !mime_types.is_empty() {
+            entry = entry.list("MimeType", &mime_types);
+        }
+
+        let ids = actions
+            .iter()
+            .enumerate()
+            .map(|(idx, action)| desktop_entry::action_id(idx, &action.name))
+            .collect::<Vec<String>>();
 
-        return Ok(true);
+        if !ids.is_empty() {
+            entry = entry.list("Actions", &ids);
+        }
+
+        for (id, action) in ids.iter().zip(actions) {
+            let mut action_exec = exec.clone();
+            action_exec.push(action.url);
+
+            entry = entry
+                .group(&format!("Desktop Action {}", id))
+                .string("Name", &action.name)
+                .exec(&action_exec);
+        }
+
+        entry
     }
 
-    pub async fn delete(&self) -> std::io::Result<()> {
+    pub async fn delete(&self) -> anyhow::Result<()> {
         let proxy = DynamicLauncherProxy::new()
             .await
-            .expect("Failed to create DynamicLauncherProxy");
+            .context("dynamic launcher portal unavailable")?;
 
         proxy
             .uninstall(
@@ -133,7 +370,7 @@ impl WebAppLauncher {
                 UninstallOptions::default(),
             )
             .await
-            .expect("Failed to uninstall");
+            .context("failed to uninstall launcher")?;
 
         if let Some(path) = crate::database_path(&format!("{}.ron", self.browser.app_id.as_ref())) {
             remove_file(path).await?;
@@ -144,3 +381,95 @@ impl WebAppLauncher {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn launcher() -> WebAppLauncher {
+        let mut browser = crate::browser::Browser::new("Mail1234");
+        browser.url = Some(String::from("https://mail.example.com"));
+
+        WebAppLauncher::new(
+            browser,
+            String::from("Mail"),
+            WebappIcon {
+                path: PathBuf::from("/tmp/mail.png"),
+                buffer: vec![137, 80, 78, 71],
+            },
+            crate::Category::Network,
+        )
+    }
+
+    #[test]
+    fn record_round_trip() {
+        let saved = launcher();
+        let (loaded, version) = parse_record(&saved.to_record().unwrap()).unwrap();
+
+        assert_eq!(version, DATABASE_VERSION);
+        assert_eq!(loaded.name, saved.name);
+        assert_eq!(loaded.category, saved.category);
+        assert_eq!(loaded.browser.app_id, saved.browser.app_id);
+        assert_eq!(loaded.browser.url, saved.browser.url);
+        assert_eq!(loaded.browser.isolated, saved.browser.isolated);
+        assert_eq!(loaded.icon.buffer, saved.icon.buffer);
+    }
+
+    #[test]
+    fn migrates_version_0() {
+        // written before records had a version, profiles were always isolated then
+        let record = r#"(
+    browser: (
+        app_id: (id: "Mail1234"),
+        window_title: Some("Mail"),
+        url: Some("https://mail.example.com"),
+        profile: "/home/user/.local/share/dev.heppen.webapps/profiles/Mail1234",
+        window_size: Some((800, 600)),
+        try_simulate_mobile: Some(false),
+    ),
+    name: "Mail",
+    icon: (path: "/tmp/mail.png", buffer: [137, 80, 78, 71]),
+    category: Network,
+)"#;
+
+        let (launcher, version) = parse_record(record).unwrap();
+
+        assert_eq!(version, 0);
+        assert_eq!(launcher.version, DATABASE_VERSION);
+        assert_eq!(launcher.name, "Mail");
+        assert_eq!(launcher.category, crate::Category::Network);
+        assert_eq!(launcher.browser.app_id.id, "Mail1234");
+        assert_eq!(launcher.browser.isolated, Some(true));
+        assert!(launcher.comment.is_none());
+    }
+
+    #[test]
+    fn declares_only_handled_mime_types() {
+        let mut launcher = launcher();
+        launcher.mime_types = Some(vec![
+            String::from("text/plain"),
+            String::from("x-scheme-handler/tel"),
+            String::from("x-scheme-handler/mailto"),
+        ]);
+
+        assert!(launcher.handled_mime_types().is_empty());
+
+        launcher.browser.protocol_handlers = Some(vec![crate::browser::ProtocolHandler {
+            protocol: String::from("mailto"),
+            url: String::from("/compose?to=%s"),
+        }]);
+
+        assert_eq!(
+            launcher.handled_mime_types(),
+            vec![String::from("x-scheme-handler/mailto")]
+        );
+    }
+
+    #[test]
+    fn rejects_newer_records() {
+        let mut record = launcher();
+        record.version = DATABASE_VERSION + 1;
+
+        assert!(parse_record(&record.to_record().unwrap()).is_err());
+    }
+}
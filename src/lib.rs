@@ -25,19 +25,45 @@ use walkdir::WalkDir;
 use crate::launcher::WebappIcon;
 
 pub mod browser;
+pub mod bundle;
+pub mod content_filter;
+pub mod desktop_entry;
+pub mod device;
+pub mod favicon;
+pub mod http;
 pub mod launcher;
 pub mod localize;
+pub mod manifest;
+pub mod permissions;
+pub mod profiles;
+pub mod url_handler;
+pub mod user_agent;
+pub mod window_state;
 
 pub const DEFAULT_WINDOW_WIDTH: WindowWidth = 800;
 pub const DEFAULT_WINDOW_HEIGHT: WindowHeight = 600;
 pub const ICON_SIZE: u32 = 42;
+pub const DEFAULT_ZOOM: u32 = 100;
+/// Zoom presets of the editor, in percent.
+pub const ZOOM_PRESETS: [u32; 12] = [50, 67, 75, 80, 90, 100, 110, 125, 150, 175, 200, 250];
 pub const REPOSITORY: &str = env!("CARGO_PKG_REPOSITORY");
 pub const CONFIG_VERSION: u64 = 1;
 pub const APP_ID: &str = "dev.heppen.webapps";
+pub const DEFAULT_SHARED_PROFILE: &str = "default";
+/// Process message carrying what the webview's injected scripts send to the browser process.
+pub const BRIDGE_MESSAGE: &str = "webapps-bridge";
+/// Native function the renderer gives main frames for sending bridge messages.
+pub const BRIDGE_FUNCTION: &str = "__webappsSend";
 pub const APP_ICON: &[u8] =
     include_bytes!("../resources/icons/hicolor/256x256/apps/dev.heppen.webapps.png");
-pub const MOBILE_UA: &str = "Mozilla/5.0 (Linux; Android 10; K) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/145.0.7632.76 Mobile Safari/537.36";
-pub const DESKTOP_UA: &str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/145.0.0.0 Safari/537.36";
+
+/// The zoom preset closest to `zoom`, so the editor can show it.
+pub fn nearest_zoom_preset(zoom: u32) -> u32 {
+    ZOOM_PRESETS
+        .into_iter()
+        .min_by_key(|preset| preset.abs_diff(zoom))
+        .unwrap_or(DEFAULT_ZOOM)
+}
 
 pub fn url_valid(url: &str) -> bool {
     if Url::parse(url).is_ok() {
@@ -782,6 +808,19 @@ impl Default for WindowSize {
 #[command(propagate_version = true, ignore_errors = true)]
 pub struct WebviewArgs {
     pub id: String,
+    /// Page to open instead of the web app's start page
+    #[arg(long = "url")]
+    #[serde(skip)]
+    pub url_flag: Option<String>,
+    #[serde(skip)]
+    pub url: Option<String>,
+}
+
+impl WebviewArgs {
+    /// Requested start page, given either as `--url` or as second argument.
+    pub fn requested_url(&self) -> Option<&str> {
+        self.url_flag.as_deref().or(self.url.as_deref())
+    }
 }
 
 impl AsRef<str> for WebviewArgs {
@@ -795,7 +834,10 @@ impl IntoIterator for WebviewArgs {
     type IntoIter = std::vec::IntoIter<String>;
 
     fn into_iter(self) -> Self::IntoIter {
-        vec![self.id.clone()].into_iter()
+        let mut args = vec![self.id];
+        args.extend(self.url);
+
+        args.into_iter()
     }
 }
 
@@ -0,0 +1,496 @@
+//! Details of a site read from its W3C web app manifest, or from the page itself
+//! when it has none.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use url::Url;
+
+use crate::{Category, http, launcher::WebappIcon};
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ManifestIcon {
+    pub src: String,
+    pub sizes: Option<String>,
+    #[serde(rename = "type")]
+    pub mime_type: Option<String>,
+    pub purpose: Option<String>,
+}
+
+impl ManifestIcon {
+    /// Icons made for masks or monochrome rendering look wrong as launcher icons.
+    fn is_regular(&self) -> bool {
+        self.purpose
+            .as_deref()
+            .is_none_or(|purpose| purpose.split_whitespace().any(|purpose| purpose == "any"))
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WebAppManifest {
+    pub name: Option<String>,
+    pub short_name: Option<String>,
+    pub start_url: Option<String>,
+    pub scope: Option<String>,
+    pub theme_color: Option<String>,
+    pub background_color: Option<String>,
+    pub display: Option<String>,
+    pub icons: Vec<ManifestIcon>,
+    pub categories: Vec<String>,
+}
+
+/// A `<link>` element of a page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtmlLink {
+    pub rel: String,
+    pub href: String,
+    pub sizes: Option<String>,
+    pub mime_type: Option<String>,
+}
+
+/// What could be found out about a site, URLs are absolute.
+#[derive(Debug, Clone, Default)]
+pub struct SiteDetails {
+    pub name: Option<String>,
+    pub start_url: Option<String>,
+    pub scope: Option<String>,
+    pub theme_color: Option<String>,
+    pub display: Option<String>,
+    pub category: Option<Category>,
+    pub icon_url: Option<String>,
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Attributes of an HTML start tag like `<link rel="icon" href=/favicon.ico>`.
+fn tag_attributes(tag: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    let mut rest = tag
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+        .trim_end_matches('/');
+
+    // skip the tag name
+    rest = rest
+        .find(char::is_whitespace)
+        .map(|idx| &rest[idx..])
+        .unwrap_or_default();
+
+    loop {
+        rest = rest.trim_start();
+
+        let name_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+
+        if name_end == 0 {
+            break;
+        }
+
+        let name = rest[..name_end].to_lowercase();
+        rest = rest[name_end..].trim_start();
+
+        let Some(value_start) = rest.strip_prefix('=') else {
+            attributes.insert(name, String::new());
+            continue;
+        };
+        let value_start = value_start.trim_start();
+
+        let (value, remaining) = match value_start.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let value = &value_start[1..];
+                let end = value.find(quote).unwrap_or(value.len());
+                (&value[..end], value.get(end + 1..).unwrap_or_default())
+            }
+            _ => {
+                let end = value_start
+                    .find(char::is_whitespace)
+                    .unwrap_or(value_start.len());
+                (&value_start[..end], &value_start[end..])
+            }
+        };
+
+        attributes.insert(name, decode_entities(value));
+        rest = remaining;
+    }
+
+    attributes
+}
+
+/// Start tags named `name` within `html`.
+fn start_tags<'a>(html: &'a str, name: &str) -> Vec<&'a str> {
+    let lowercase = html.to_ascii_lowercase();
+    let opening = format!("<{}", name);
+
+    lowercase
+        .match_indices(&opening)
+        .filter(|(idx, _)| {
+            lowercase[idx + opening.len()..]
+                .starts_with(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        })
+        .filter_map(|(idx, _)| {
+            let end = html[idx..].find('>')?;
+            Some(&html[idx..=idx + end])
+        })
+        .collect()
+}
+
+/// Every `<link>` of a page which has a `rel` and an `href`.
+pub fn html_links(html: &str) -> Vec<HtmlLink> {
+    start_tags(html, "link")
+        .into_iter()
+        .map(tag_attributes)
+        .filter_map(|mut attributes| {
+            Some(HtmlLink {
+                rel: attributes.remove("rel")?.to_lowercase(),
+                href: attributes.remove("href").filter(|href| !href.is_empty())?,
+                sizes: attributes.remove("sizes"),
+                mime_type: attributes.remove("type"),
+            })
+        })
+        .collect()
+}
+
+fn html_meta(html: &str, name: &str) -> Option<String> {
+    start_tags(html, "meta")
+        .into_iter()
+        .map(tag_attributes)
+        .find(|attributes| {
+            attributes
+                .get("name")
+                .is_some_and(|value| value.eq_ignore_ascii_case(name))
+        })
+        .and_then(|mut attributes| attributes.remove("content"))
+}
+
+fn html_title(html: &str) -> Option<String> {
+    let lowercase = html.to_ascii_lowercase();
+    let start = lowercase.find("<title")?;
+    let start = start + lowercase[start..].find('>')? + 1;
+    let end = start + lowercase[start..].find("</title")?;
+
+    Some(decode_entities(html[start..end].trim())).filter(|title| !title.is_empty())
+}
+
+/// Largest edge length in a `sizes` value like `192x192 512x512`, `any` for scalable icons.
+pub fn icon_size(sizes: Option<&str>) -> Option<u32> {
+    sizes?
+        .split_whitespace()
+        .filter_map(|size| {
+            if size.eq_ignore_ascii_case("any") {
+                return Some(u32::MAX);
+            }
+
+            let (width, height) = size
+                .to_lowercase()
+                .split_once('x')
+                .map(|(width, height)| (width.parse::<u32>().ok(), height.parse::<u32>().ok()))?;
+
+            Some(width?.max(height?))
+        })
+        .max()
+}
+
+fn category(categories: &[String]) -> Option<Category> {
+    categories
+        .iter()
+        .find_map(|category| match category.to_lowercase().as_str() {
+            "games" => Some(Category::Game),
+            "education" | "books" | "kids" => Some(Category::Education),
+            "music" => Some(Category::Audio),
+            "entertainment" => Some(Category::AudioVideo),
+            "photo" => Some(Category::Graphics),
+            "productivity" | "business" | "finance" => Some(Category::Office),
+            "social" | "news" | "shopping" | "travel" | "navigation" | "weather" => {
+                Some(Category::Network)
+            }
+            "developer" | "developer tools" => Some(Category::Development),
+            "utilities" | "security" => Some(Category::Utility),
+            _ => None,
+        })
+}
+
+impl WebAppManifest {
+    pub fn parse(json: &str) -> anyhow::Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// The icon best suited for a launcher, scalable ones first, otherwise the largest.
+    pub fn best_icon(&self) -> Option<&ManifestIcon> {
+        self.icons
+            .iter()
+            .filter(|icon| icon.is_regular() && !icon.src.is_empty())
+            .max_by_key(|icon| {
+                let scalable = icon.mime_type.as_deref() == Some("image/svg+xml")
+                    || icon.src.ends_with(".svg");
+
+                (
+                    scalable,
+                    icon_size(icon.sizes.as_deref()).unwrap_or_default(),
+                )
+            })
+    }
+
+    /// Icons with their `src` resolved against the manifest's own URL.
+    pub fn icon_urls(&self, manifest_url: &Url) -> Vec<String> {
+        self.icons
+            .iter()
+            .filter(|icon| icon.is_regular())
+            .filter_map(|icon| manifest_url.join(&icon.src).ok())
+            .map(String::from)
+            .collect()
+    }
+
+    /// Details with URLs resolved against the manifest's own URL.
+    fn details(&self, manifest_url: &Url) -> SiteDetails {
+        let resolve = |url: &str| manifest_url.join(url).ok().map(String::from);
+
+        SiteDetails {
+            name: self.name.clone().or_else(|| self.short_name.clone()),
+            start_url: self.start_url.as_deref().and_then(resolve),
+            scope: self.scope.as_deref().and_then(resolve),
+            theme_color: self.theme_color.clone(),
+            display: self.display.clone(),
+            category: category(&self.categories),
+            icon_url: self.best_icon().and_then(|icon| resolve(&icon.src)),
+        }
+    }
+}
+
+/// The manifest `links` point to, together with its URL.
+pub fn fetch_manifest(page_url: &Url, links: &[HtmlLink]) -> Option<(WebAppManifest, Url)> {
+    let manifest_url = links
+        .iter()
+        .find(|link| link.rel.split_whitespace().any(|rel| rel == "manifest"))
+        .and_then(|link| page_url.join(&link.href).ok())?;
+
+    let manifest =
+        http::get_text(manifest_url.as_str()).and_then(|json| WebAppManifest::parse(&json));
+
+    match manifest {
+        Ok(manifest) => Some((manifest, manifest_url)),
+        Err(e) => {
+            eprintln!("Failed to read manifest {}: {}", manifest_url, e);
+            None
+        }
+    }
+}
+
+/// Reads the details of the site at `page_url`, through its manifest when it links one.
+pub fn discover(page_url: &str) -> anyhow::Result<SiteDetails> {
+    let page_url = Url::parse(page_url)?;
+    let html = http::get_text(page_url.as_str())?;
+    let links = html_links(&html);
+
+    let manifest = fetch_manifest(&page_url, &links)
+        .map(|(manifest, manifest_url)| manifest.details(&manifest_url))
+        .unwrap_or_default();
+
+    // the page fills in what the manifest left out
+    let page_icon = links
+        .iter()
+        .filter(|link| {
+            link.rel
+                .split_whitespace()
+                .any(|rel| rel == "icon" || rel == "apple-touch-icon")
+        })
+        .max_by_key(|link| icon_size(link.sizes.as_deref()).unwrap_or_default())
+        .and_then(|link| page_url.join(&link.href).ok())
+        .map(String::from);
+
+    Ok(SiteDetails {
+        name: manifest.name.or_else(|| html_title(&html)),
+        start_url: manifest.start_url,
+        scope: manifest.scope,
+        theme_color: manifest
+            .theme_color
+            .or_else(|| html_meta(&html, "theme-color")),
+        display: manifest.display,
+        category: manifest.category,
+        icon_url: manifest.icon_url.or(page_icon),
+    })
+}
+
+/// Downloads an icon into the icons folder.
+pub fn save_icon(icon_url: &str) -> anyhow::Result<WebappIcon> {
+    let Some(location) = crate::icons_location() else {
+        anyhow::bail!("cannot locate icons directory");
+    };
+
+    let candidate = crate::favicon::download_icon(icon_url, &location)?;
+    let buffer = std::fs::read(&candidate.path)?;
+
+    Ok(WebappIcon {
+        path: candidate.path,
+        buffer,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+    };
+
+    const PAGE: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+  <title>Example &amp; Co</title>
+  <meta name="theme-color" content="#112233">
+  <link rel="icon" href="/favicon-32.png" sizes="32x32">
+  <link rel="apple-touch-icon" href="/touch.png" sizes="180x180">
+  <LINK REL="Manifest" HREF="/app.webmanifest">
+</head>
+</html>"##;
+
+    const MANIFEST: &str = r##"{
+  "name": "Example",
+  "start_url": "/inbox?source=pwa",
+  "scope": "/",
+  "display": "standalone",
+  "categories": ["productivity"],
+  "icons": [
+    { "src": "icons/192.png", "sizes": "192x192" },
+    { "src": "icons/512.png", "sizes": "512x512" },
+    { "src": "icons/mask.png", "sizes": "1024x1024", "purpose": "maskable" }
+  ]
+}"##;
+
+    /// Serves `routes` on a local port, returns the base URL.
+    fn serve(routes: Vec<(&'static str, &'static str, &'static str)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind fixture server");
+        let address = listener.local_addr().expect("fixture server address");
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming().filter_map(|stream| stream.ok()) {
+                let mut reader = BufReader::new(&stream);
+                let mut request_line = String::new();
+                if reader.read_line(&mut request_line).is_err() {
+                    continue;
+                }
+
+                // the headers aren't needed, only read past them
+                let mut header = String::new();
+                while reader.read_line(&mut header).is_ok_and(|read| read > 2) {
+                    header.clear();
+                }
+
+                let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+                let response = match routes.iter().find(|(route, _, _)| *route == path) {
+                    Some((_, content_type, body)) => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+                         Connection: close\r\n\r\n{}",
+                        content_type,
+                        body.len(),
+                        body
+                    ),
+                    None => String::from(
+                        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    ),
+                };
+
+                let _ = (&stream).write_all(response.as_bytes());
+            }
+        });
+
+        format!("http://{}", address)
+    }
+
+    #[test]
+    fn reads_tag_attributes() {
+        let attributes = tag_attributes(
+            "<link rel=\"icon\" href=/favicon.ico sizes='32x32' title=\"a &amp; b\" async/>",
+        );
+
+        assert_eq!(attributes.get("rel").map(String::as_str), Some("icon"));
+        assert_eq!(
+            attributes.get("href").map(String::as_str),
+            Some("/favicon.ico")
+        );
+        assert_eq!(attributes.get("sizes").map(String::as_str), Some("32x32"));
+        assert_eq!(attributes.get("title").map(String::as_str), Some("a & b"));
+        assert_eq!(attributes.get("async").map(String::as_str), Some(""));
+        assert!(!attributes.contains_key("link"));
+    }
+
+    #[test]
+    fn finds_links() {
+        let html = "<LINK REL=\"Icon\" HREF=\"/a.png\" type=image/png>\
+                    <link rel=\"stylesheet\">\
+                    <linker rel=\"icon\" href=\"/b.png\">\
+                    <link rel=\"manifest\" href=\"/app.json\" />";
+
+        assert_eq!(
+            html_links(html),
+            [
+                HtmlLink {
+                    rel: String::from("icon"),
+                    href: String::from("/a.png"),
+                    sizes: None,
+                    mime_type: Some(String::from("image/png")),
+                },
+                HtmlLink {
+                    rel: String::from("manifest"),
+                    href: String::from("/app.json"),
+                    sizes: None,
+                    mime_type: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_icon_sizes() {
+        assert_eq!(icon_size(Some("192x192 512x512")), Some(512));
+        assert_eq!(icon_size(Some("48X32")), Some(48));
+        assert_eq!(icon_size(Some("any")), Some(u32::MAX));
+        assert_eq!(icon_size(Some("large")), None);
+        assert_eq!(icon_size(None), None);
+    }
+
+    #[test]
+    fn discovers_site_through_manifest() {
+        let base = serve(vec![
+            ("/", "text/html", PAGE),
+            ("/app.webmanifest", "application/manifest+json", MANIFEST),
+        ]);
+
+        let details = discover(&format!("{}/", base)).expect("discover fixture site");
+
+        assert_eq!(details.name.as_deref(), Some("Example"));
+        assert_eq!(
+            details.start_url,
+            Some(format!("{}/inbox?source=pwa", base))
+        );
+        assert_eq!(details.scope, Some(format!("{}/", base)));
+        assert_eq!(details.display.as_deref(), Some("standalone"));
+        assert_eq!(details.category, Some(Category::Office));
+        // masks don't make launcher icons
+        assert_eq!(details.icon_url, Some(format!("{}/icons/512.png", base)));
+        assert_eq!(details.theme_color.as_deref(), Some("#112233"));
+    }
+
+    #[test]
+    fn discovers_site_without_manifest() {
+        let page = "<title>Plain</title>\
+                    <link rel=\"icon\" href=\"/favicon-32.png\" sizes=\"32x32\">\
+                    <link rel=\"apple-touch-icon\" href=\"/touch.png\" sizes=\"180x180\">";
+        let base = serve(vec![("/", "text/html", page)]);
+
+        let details = discover(&format!("{}/", base)).expect("discover fixture site");
+
+        assert_eq!(details.name.as_deref(), Some("Plain"));
+        assert_eq!(details.start_url, None);
+        assert_eq!(details.icon_url, Some(format!("{}/touch.png", base)));
+    }
+}
@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs::create_dir_all, path::PathBuf};
+
+use crate::{APP_ID, fl};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub enum Permission {
+    Camera,
+    Microphone,
+    ScreenCapture,
+    Geolocation,
+    Clipboard,
+}
+
+impl Permission {
+    pub fn name(&self) -> String {
+        match self {
+            Permission::Camera => fl!("permission-camera"),
+            Permission::Microphone => fl!("permission-microphone"),
+            Permission::ScreenCapture => fl!("permission-screen-capture"),
+            Permission::Geolocation => fl!("permission-geolocation"),
+            Permission::Clipboard => fl!("permission-clipboard"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Decision {
+    Allow,
+    Deny,
+}
+
+/// Permission decisions of a web app, by the origin which asked.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SitePermissions {
+    pub sites: BTreeMap<String, BTreeMap<Permission, Decision>>,
+}
+
+pub fn permissions_path(app_id: &str) -> Option<PathBuf> {
+    let xdg_data = dirs::data_dir()?;
+
+    Some(
+        xdg_data
+            .join(APP_ID)
+            .join("permissions")
+            .join(format!("{}.ron", app_id)),
+    )
+}
+
+impl SitePermissions {
+    pub fn load(app_id: &str) -> Self {
+        permissions_path(app_id)
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| ron::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, app_id: &str) -> anyhow::Result<()> {
+        let Some(path) = permissions_path(app_id) else {
+            anyhow::bail!("cannot locate permissions directory");
+        };
+
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+
+        let content = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, content)?;
+
+        Ok(())
+    }
+
+    pub fn delete(app_id: &str) {
+        if let Some(path) = permissions_path(app_id) {
+            if path.exists() {
+                if let Err(e) = std::fs::remove_file(path) {
+                    eprintln!("Failed to delete permissions: {}", e);
+                }
+            }
+        }
+    }
+
+    pub fn decision(&self, origin: &str, permission: Permission) -> Option<Decision> {
+        self.sites.get(origin)?.get(&permission).copied()
+    }
+
+    pub fn set(&mut self, origin: &str, permission: Permission, decision: Decision) {
+        self.sites
+            .entry(origin.to_string())
+            .or_default()
+            .insert(permission, decision);
+    }
+
+    pub fn revoke(&mut self, origin: &str, permission: Permission) {
+        if let Some(site) = self.sites.get_mut(origin) {
+            site.remove(&permission);
+
+            if site.is_empty() {
+                self.sites.remove(origin);
+            }
+        }
+    }
+
+    /// Combined decision for a request, `None` when the user needs to be asked.
+    pub fn resolve(&self, origin: &str, permissions: &[Permission]) -> Option<Decision> {
+        let decisions = permissions
+            .iter()
+            .map(|permission| self.decision(origin, *permission))
+            .collect::<Vec<Option<Decision>>>();
+
+        if decisions.contains(&Some(Decision::Deny)) {
+            return Some(Decision::Deny);
+        }
+
+        if decisions
+            .iter()
+            .all(|decision| *decision == Some(Decision::Allow))
+        {
+            return Some(Decision::Allow);
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGIN: &str = "https://meet.example.com";
+
+    #[test]
+    fn asks_until_every_permission_is_allowed() {
+        let mut permissions = SitePermissions::default();
+        let requested = [Permission::Camera, Permission::Microphone];
+
+        assert_eq!(permissions.resolve(ORIGIN, &requested), None);
+
+        permissions.set(ORIGIN, Permission::Camera, Decision::Allow);
+        assert_eq!(permissions.resolve(ORIGIN, &requested), None);
+
+        permissions.set(ORIGIN, Permission::Microphone, Decision::Allow);
+        assert_eq!(
+            permissions.resolve(ORIGIN, &requested),
+            Some(Decision::Allow)
+        );
+    }
+
+    #[test]
+    fn any_denial_denies() {
+        let mut permissions = SitePermissions::default();
+        permissions.set(ORIGIN, Permission::Camera, Decision::Allow);
+        permissions.set(ORIGIN, Permission::Microphone, Decision::Deny);
+
+        assert_eq!(
+            permissions.resolve(ORIGIN, &[Permission::Camera, Permission::Microphone]),
+            Some(Decision::Deny)
+        );
+        assert_eq!(
+            permissions.resolve(ORIGIN, &[Permission::Microphone, Permission::Geolocation]),
+            Some(Decision::Deny)
+        );
+    }
+
+    #[test]
+    fn decisions_belong_to_their_origin() {
+        let mut permissions = SitePermissions::default();
+        permissions.set(ORIGIN, Permission::Camera, Decision::Allow);
+
+        assert_eq!(
+            permissions.resolve("https://example.com", &[Permission::Camera]),
+            None
+        );
+
+        permissions.revoke(ORIGIN, Permission::Camera);
+        assert_eq!(permissions.resolve(ORIGIN, &[Permission::Camera]), None);
+        assert!(permissions.sites.is_empty());
+    }
+}
@@ -0,0 +1,380 @@
+use std::{
+    fs::create_dir_all,
+    os::unix::net::UnixStream,
+    path::{Component, Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+use crate::{
+    APP_ID,
+    browser::profile_name,
+    launcher::{WebAppLauncher, installed_webapps},
+};
+
+// directories inside a CEF profile which only hold cached data
+const CACHE_ENTRIES: &[&str] = &[
+    "Cache",
+    "Code Cache",
+    "GPUCache",
+    "GrShaderCache",
+    "ShaderCache",
+    "DawnGraphiteCache",
+    "DawnWebGPUCache",
+    "Service Worker/CacheStorage",
+    "Service Worker/ScriptCache",
+];
+
+const COOKIE_ENTRIES: &[&str] = &["Cookies", "Cookies-journal"];
+
+pub fn shared_profiles_location() -> Option<PathBuf> {
+    if let Some(xdg_data) = dirs::data_dir() {
+        let final_path = xdg_data.join(APP_ID).join("shared-profiles");
+
+        if !final_path.exists() {
+            if let Err(e) = create_dir_all(&final_path) {
+                eprintln!("Failed to create shared profiles directory: {}", e);
+                return None;
+            }
+        }
+
+        return Some(final_path);
+    }
+
+    None
+}
+
+pub fn format_size(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, units[unit])
+    } else {
+        format!("{:.1} {}", size, units[unit])
+    }
+}
+
+fn is_dir_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+
+    matches!(components.next(), Some(Component::Normal(_))) && components.next().is_none()
+}
+
+/// Profiles found in `location`, keeping the names of their directories as they are,
+/// even those a profile created here would be named differently.
+fn read_profiles(location: &Path, isolated: bool) -> Vec<Profile> {
+    let mut profiles = Vec::new();
+
+    if let Ok(entries) = std::fs::read_dir(location) {
+        for entry in entries.flatten() {
+            if entry.path().is_dir() {
+                profiles.push(Profile {
+                    name: entry.file_name().to_string_lossy().to_string(),
+                    path: entry.path(),
+                    isolated,
+                });
+            }
+        }
+    }
+
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    profiles
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    pub path: PathBuf,
+    /// Profile of a single web app, named after its app id
+    pub isolated: bool,
+}
+
+impl Profile {
+    pub fn new(name: &str) -> Self {
+        let location = dirs::data_dir()
+            .unwrap_or_default()
+            .join(APP_ID)
+            .join("shared-profiles");
+
+        // a directory made outside of the app keeps its name, even one we wouldn't pick
+        let name = if is_dir_name(name) && location.join(name).is_dir() {
+            name.to_string()
+        } else {
+            profile_name(name)
+        };
+        let path = location.join(&name);
+
+        Self {
+            name,
+            path,
+            isolated: false,
+        }
+    }
+
+    /// Isolated profiles of web apps, including those left behind by deleted ones.
+    pub fn list_isolated() -> Vec<Self> {
+        dirs::data_dir()
+            .map(|dir| read_profiles(&dir.join(APP_ID).join("profiles"), true))
+            .unwrap_or_default()
+    }
+
+    pub fn list() -> Vec<Self> {
+        shared_profiles_location()
+            .map(|location| read_profiles(&location, false))
+            .unwrap_or_default()
+    }
+
+    pub fn exists(&self) -> bool {
+        self.path.exists()
+    }
+
+    pub fn create(name: &str) -> anyhow::Result<Self> {
+        let profile = Self::new(name);
+
+        if profile.exists() {
+            anyhow::bail!("profile {} already exists", profile.name);
+        }
+
+        create_dir_all(&profile.path)?;
+
+        Ok(profile)
+    }
+
+    pub fn users(&self) -> Vec<WebAppLauncher> {
+        installed_webapps()
+            .into_iter()
+            .filter(|launcher| launcher.browser.profile == self.path)
+            .collect()
+    }
+
+    /// Whether a web app using the profile is running. Every running webview
+    /// listens on a socket in its profile directory.
+    pub fn is_running(&self) -> bool {
+        let Ok(entries) = std::fs::read_dir(&self.path) else {
+            return false;
+        };
+
+        entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "sock"))
+            .any(|path| UnixStream::connect(path).is_ok())
+    }
+
+    fn ensure_stopped(&self) -> anyhow::Result<()> {
+        if self.is_running() {
+            anyhow::bail!("profile {} is in use by a running web app", self.name);
+        }
+
+        Ok(())
+    }
+
+    pub fn disk_usage(&self) -> u64 {
+        WalkDir::new(&self.path)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.metadata().ok())
+            .filter(|metadata| metadata.is_file())
+            .map(|metadata| metadata.len())
+            .sum()
+    }
+
+    pub fn rename(&self, new_name: &str) -> anyhow::Result<Self> {
+        if self.isolated {
+            anyhow::bail!("isolated profiles are named after their web app");
+        }
+
+        // CEF of a running web app keeps writing to the old location
+        self.ensure_stopped()?;
+
+        let renamed = Self::new(new_name);
+
+        if renamed.exists() {
+            anyhow::bail!("profile {} already exists", renamed.name);
+        }
+
+        std::fs::rename(&self.path, &renamed.path)?;
+
+        for mut launcher in self.users() {
+            launcher.browser.set_profile(false, &renamed.name);
+            launcher.save()?;
+        }
+
+        Ok(renamed)
+    }
+
+    pub fn duplicate(&self, new_name: &str) -> anyhow::Result<Self> {
+        // a running web app leaves its databases half written
+        self.ensure_stopped()?;
+
+        let copy = Self::create(new_name)?;
+
+        for entry in WalkDir::new(&self.path).into_iter().filter_map(|e| e.ok()) {
+            let Ok(relative) = entry.path().strip_prefix(&self.path) else {
+                continue;
+            };
+            let target = copy.path.join(relative);
+
+            if entry.file_type().is_dir() {
+                create_dir_all(&target)?;
+            } else if entry.file_type().is_file() {
+                std::fs::copy(entry.path(), &target)?;
+            }
+        }
+
+        Ok(copy)
+    }
+
+    pub fn clear_cache(&self) -> anyhow::Result<()> {
+        self.ensure_stopped()?;
+        self.remove_entries(CACHE_ENTRIES)?;
+
+        Ok(())
+    }
+
+    pub fn clear_cookies(&self) -> anyhow::Result<()> {
+        self.ensure_stopped()?;
+        self.remove_entries(COOKIE_ENTRIES)?;
+
+        Ok(())
+    }
+
+    pub fn delete(&self) -> anyhow::Result<()> {
+        self.ensure_stopped()?;
+
+        let users = self.users();
+
+        if !users.is_empty() {
+            anyhow::bail!("profile {} is used by {} web apps", self.name, users.len());
+        }
+
+        if self.exists() {
+            std::fs::remove_dir_all(&self.path)?;
+        }
+
+        Ok(())
+    }
+
+    fn remove_entries(&self, entries: &[&str]) -> std::io::Result<()> {
+        // CEF keeps its data both in the root cache path and in the `cache` subdirectory
+        for root in [self.path.clone(), self.path.join("cache")] {
+            for entry in entries {
+                let path = root.join(entry);
+
+                if path.is_dir() {
+                    std::fs::remove_dir_all(&path)?;
+                } else if path.is_file() {
+                    std::fs::remove_file(&path)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("webapps-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn lists_directories_by_their_real_path() {
+        let location = scratch_dir("profiles");
+        for name in [" Work ", "Personal"] {
+            create_dir_all(location.join(name)).unwrap();
+        }
+        std::fs::write(location.join("notes.txt"), "").unwrap();
+
+        let profiles = read_profiles(&location, false);
+
+        assert_eq!(
+            profiles
+                .iter()
+                .map(|profile| profile.name.as_str())
+                .collect::<Vec<_>>(),
+            [" Work ", "Personal"]
+        );
+        assert!(profiles.iter().all(Profile::exists));
+        assert_eq!(profiles[0].path, location.join(" Work "));
+
+        std::fs::remove_dir_all(location).unwrap();
+    }
+
+    #[test]
+    fn only_plain_names_are_dir_names() {
+        assert!(is_dir_name(" Work "));
+        assert!(!is_dir_name(".."));
+        assert!(!is_dir_name("."));
+        assert!(!is_dir_name("a/b"));
+        assert!(!is_dir_name("/work"));
+        assert!(!is_dir_name(""));
+    }
+
+    #[test]
+    fn running_profiles_are_left_alone() {
+        let path = scratch_dir("running");
+        create_dir_all(path.join("Cache")).unwrap();
+        let profile = Profile {
+            name: String::from("Work"),
+            path: path.clone(),
+            isolated: false,
+        };
+
+        let listener = UnixListener::bind(path.join("Mail1234.sock")).unwrap();
+        assert!(profile.is_running());
+        assert!(profile.duplicate("Work copy").is_err());
+        assert!(profile.rename("Work2").is_err());
+        assert!(profile.clear_cache().is_err());
+        assert!(path.join("Cache").exists());
+
+        drop(listener);
+        assert!(!profile.is_running());
+        profile.clear_cache().unwrap();
+        assert!(!path.join("Cache").exists());
+
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn clears_only_cached_data() {
+        let path = scratch_dir("cache");
+        for entry in ["Cache", "cache/Code Cache", "Local Storage"] {
+            create_dir_all(path.join(entry)).unwrap();
+        }
+        for entry in ["Cookies", "cache/Cookies-journal", "Preferences"] {
+            std::fs::write(path.join(entry), "").unwrap();
+        }
+        let profile = Profile {
+            name: String::from("Work"),
+            path: path.clone(),
+            isolated: false,
+        };
+
+        profile.clear_cache().unwrap();
+        assert!(!path.join("Cache").exists());
+        assert!(!path.join("cache/Code Cache").exists());
+        assert!(path.join("Local Storage").exists());
+        assert!(path.join("Cookies").exists());
+
+        profile.clear_cookies().unwrap();
+        assert!(!path.join("Cookies").exists());
+        assert!(!path.join("cache/Cookies-journal").exists());
+        assert!(path.join("Preferences").exists());
+
+        std::fs::remove_dir_all(path).unwrap();
+    }
+}
@@ -0,0 +1,403 @@
+use anyhow::Context as _;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{
+    APP_ID, desktop_entry,
+    launcher::{WebAppLauncher, read_installed_webapps},
+};
+
+/// Matches `text` against `pattern`, where `*` stands for any sequence of characters.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+
+    let Some(first) = parts.next() else {
+        return false;
+    };
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts = parts.collect::<Vec<&str>>();
+    let Some((last, middle)) = parts.split_last() else {
+        // no wildcard at all
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+/// Matches a host against `*`, `*.example.com` covering the domain and its subdomains,
+/// or a plain host.
+fn host_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+
+    match pattern.strip_prefix("*.") {
+        Some(domain) => {
+            host == domain
+                || host
+                    .strip_suffix(domain)
+                    .is_some_and(|subdomain| subdomain.ends_with('.'))
+        }
+        None => pattern == "*" || host == pattern,
+    }
+}
+
+/// Matches `url` against a pattern like `https://mail.example.com/*`. Scheme, host and
+/// port are compared as parts of the parsed url, the wildcards of the path only apply
+/// to the path and query. `https://mail.example.com*` covers every page of the host.
+pub fn pattern_matches(pattern: &str, url: &str) -> bool {
+    let Ok(url) = url::Url::parse(url) else {
+        return false;
+    };
+    let Some((scheme, rest)) = pattern.split_once("://") else {
+        return false;
+    };
+
+    if !scheme.eq_ignore_ascii_case(url.scheme()) {
+        return false;
+    }
+
+    let (authority, path) = match rest.find('/') {
+        Some(idx) => rest.split_at(idx),
+        None if rest.len() > 1 && rest.ends_with('*') => (&rest[..rest.len() - 1], "*"),
+        None => (rest, "/"),
+    };
+
+    // a port is digits after the last colon, those of IPv6 hosts are within brackets
+    let (host, port) = match authority
+        .rsplit_once(':')
+        .filter(|(_, port)| !port.is_empty() && port.chars().all(|c| c.is_ascii_digit()))
+    {
+        Some((host, port)) => (host, port.parse::<u16>().ok()),
+        None => (authority, None),
+    };
+
+    let port_matches = match port {
+        Some(port) => url.port_or_known_default() == Some(port),
+        None => url.port().is_none(),
+    };
+
+    port_matches
+        && url
+            .host_str()
+            .is_some_and(|url_host| host_matches(host, url_host))
+        && glob_matches(path, &url[url::Position::BeforePath..])
+}
+
+/// Scheme claimed by a pattern, e.g. `https` for `https://example.com/*`.
+pub fn pattern_scheme(pattern: &str) -> Option<String> {
+    let (scheme, _) = pattern.split_once(':')?;
+
+    let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+
+    valid.then(|| scheme.to_ascii_lowercase())
+}
+
+/// Default pattern covering everything below the web app's URL.
+pub fn default_pattern(url: &str) -> Option<String> {
+    let url = url::Url::parse(url).ok()?;
+
+    Some(format!("{}/*", url.origin().ascii_serialization()))
+}
+
+/// Finds the web app claiming `url`. The most specific pattern wins when several apps match.
+pub fn find_handler(url: &str) -> Option<WebAppLauncher> {
+    best_handler(read_installed_webapps(), url)
+}
+
+fn best_handler(launchers: Vec<WebAppLauncher>, url: &str) -> Option<WebAppLauncher> {
+    launchers
+        .into_iter()
+        .filter_map(|launcher| {
+            let specificity = launcher
+                .browser
+                .url_handlers
+                .iter()
+                .flatten()
+                .filter(|pattern| pattern_matches(pattern, url))
+                .map(|pattern| pattern.replace('*', "").len())
+                .max()?;
+
+            Some((specificity, launcher))
+        })
+        .max_by_key(|(specificity, _)| *specificity)
+        .map(|(_, launcher)| launcher)
+}
+
+/// Opens `url` in the web app claiming it, or falls back to a regular browser.
+pub fn dispatch(url: &str) -> anyhow::Result<()> {
+    if let Some(launcher) = find_handler(url) {
+        tracing::info!("opening {} in {}", url, launcher.name);
+        return launcher.browser.launch(Some(url));
+    }
+
+    // browsers of the host can't be seen or started from the sandbox, xdg-open there asks
+    // the OpenURI portal, which opens the url on the host
+    if Path::new("/.flatpak-info").exists() {
+        Command::new("xdg-open").arg(url).spawn()?;
+        return Ok(());
+    }
+
+    if let Ok(browser) = std::env::var("BROWSER") {
+        Command::new(browser).arg(url).spawn()?;
+        return Ok(());
+    }
+
+    // xdg-open would hand the url straight back to us when the dispatcher is the default,
+    // so the browser is looked up and started here
+    let scheme = pattern_scheme(url).context("url has no scheme")?;
+    let args = browser_candidates(&scheme)
+        .into_iter()
+        .find_map(|desktop_id| browser_command(&desktop_id, url))
+        .with_context(|| format!("no web app or browser handles {}", url))?;
+
+    tracing::info!("opening {} with {}", url, args[0]);
+    Command::new(&args[0]).args(&args[1..]).spawn()?;
+
+    Ok(())
+}
+
+/// `$XDG_DATA_HOME` followed by `$XDG_DATA_DIRS`.
+fn data_dirs() -> Vec<PathBuf> {
+    let system = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| String::from("/usr/local/share:/usr/share"));
+
+    dirs::data_dir()
+        .into_iter()
+        .chain(std::env::split_paths(&system))
+        .collect()
+}
+
+/// `$XDG_CONFIG_HOME` followed by `$XDG_CONFIG_DIRS`.
+fn config_dirs() -> Vec<PathBuf> {
+    let system = std::env::var("XDG_CONFIG_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| String::from("/etc/xdg"));
+
+    dirs::config_dir()
+        .into_iter()
+        .chain(std::env::split_paths(&system))
+        .collect()
+}
+
+/// Desktop ids listed for `mime_type` under `group` of a `mimeapps.list` or `mimeinfo.cache`.
+fn mime_associations(path: &Path, group: &str, mime_type: &str) -> Vec<String> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+
+    let mut in_group = false;
+    for line in content.lines() {
+        let line = line.trim();
+
+        if line.starts_with('[') {
+            in_group = line == group;
+            continue;
+        }
+
+        if let Some((key, value)) = line.split_once('=') {
+            if in_group && key.trim() == mime_type {
+                return value
+                    .split(';')
+                    .map(str::trim)
+                    .filter(|id| !id.is_empty())
+                    .map(String::from)
+                    .collect();
+            }
+        }
+    }
+
+    Vec::new()
+}
+
+/// Desktop ids which could open `scheme` urls, most preferred first. The dispatcher
+/// and the web apps are left out.
+fn browser_candidates(scheme: &str) -> Vec<String> {
+    let mime_type = format!("x-scheme-handler/{}", scheme);
+    let mut candidates = Vec::new();
+
+    if let Ok(output) = Command::new("xdg-settings")
+        .args(["get", "default-web-browser"])
+        .output()
+    {
+        candidates.push(String::from_utf8_lossy(&output.stdout).trim().to_string());
+    }
+
+    let mimeapps = config_dirs()
+        .into_iter()
+        .chain(data_dirs().into_iter().map(|dir| dir.join("applications")))
+        .map(|dir| dir.join("mimeapps.list"))
+        .collect::<Vec<PathBuf>>();
+
+    for group in ["[Default Applications]", "[Added Associations]"] {
+        for path in &mimeapps {
+            candidates.extend(mime_associations(path, group, &mime_type));
+        }
+    }
+
+    for dir in data_dirs() {
+        let cache = dir.join("applications").join("mimeinfo.cache");
+        candidates.extend(mime_associations(&cache, "[MIME Cache]", &mime_type));
+    }
+
+    let own_prefix = format!("{}.", APP_ID);
+    let mut seen = Vec::new();
+    candidates.retain(|id| {
+        let keep = !id.is_empty() && !id.starts_with(&own_prefix) && !seen.contains(id);
+        seen.push(id.clone());
+        keep
+    });
+
+    candidates
+}
+
+/// Command line of the desktop entry `desktop_id` opening `url`.
+fn browser_command(desktop_id: &str, url: &str) -> Option<Vec<String>> {
+    let content = data_dirs()
+        .into_iter()
+        .find_map(|dir| std::fs::read_to_string(dir.join("applications").join(desktop_id)).ok())?;
+
+    let exec = desktop_entry::read_key(&content, "Exec")?;
+    let args = desktop_entry::expand_exec(&exec, url);
+
+    // a lone url would be run as the program
+    (args.len() > 1).then_some(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn launcher(id: &str, patterns: &[&str]) -> WebAppLauncher {
+        let mut browser = crate::browser::Browser::new(id);
+        browser.url_handlers = Some(patterns.iter().map(|p| p.to_string()).collect());
+
+        WebAppLauncher::new(
+            browser,
+            id.to_string(),
+            crate::launcher::WebappIcon {
+                path: PathBuf::from("/tmp/icon.png"),
+                buffer: Vec::new(),
+            },
+            crate::Category::Network,
+        )
+    }
+
+    #[test]
+    fn matches_path_wildcards() {
+        let pattern = "https://mail.example.com/inbox/*";
+
+        assert!(pattern_matches(pattern, "https://mail.example.com/inbox/1"));
+        assert!(pattern_matches(
+            pattern,
+            "https://mail.example.com/inbox/?q=a"
+        ));
+        assert!(!pattern_matches(pattern, "https://mail.example.com/sent/1"));
+        assert!(!pattern_matches(pattern, "http://mail.example.com/inbox/1"));
+    }
+
+    #[test]
+    fn host_must_match_exactly() {
+        for pattern in ["https://mail.example.com*", "https://mail.example.com/*"] {
+            assert!(pattern_matches(pattern, "https://mail.example.com/"));
+            assert!(pattern_matches(pattern, "https://MAIL.example.com/inbox"));
+            assert!(!pattern_matches(
+                pattern,
+                "https://mail.example.com.evil.net/"
+            ));
+            assert!(!pattern_matches(
+                pattern,
+                "https://mail.example.com@evil.net/"
+            ));
+            assert!(!pattern_matches(
+                pattern,
+                "https://evil.net/mail.example.com/"
+            ));
+            assert!(!pattern_matches(pattern, "https://mail.example.com:8443/"));
+        }
+    }
+
+    #[test]
+    fn matches_subdomains_and_ports() {
+        let pattern = "https://*.example.com/*";
+
+        assert!(pattern_matches(pattern, "https://example.com/"));
+        assert!(pattern_matches(
+            pattern,
+            "https://accounts.example.com/login"
+        ));
+        assert!(!pattern_matches(pattern, "https://badexample.com/"));
+        assert!(!pattern_matches(pattern, "https://example.com.evil.net/"));
+
+        assert!(pattern_matches(
+            "http://localhost:3000/*",
+            "http://localhost:3000/app"
+        ));
+        assert!(!pattern_matches(
+            "http://localhost:3000/*",
+            "http://localhost:4000/app"
+        ));
+        assert!(pattern_matches("http://[::1]:3000/*", "http://[::1]:3000/"));
+    }
+
+    #[test]
+    fn origin_patterns() {
+        // allowed origins are matched against the serialized origin
+        assert!(pattern_matches(
+            "https://accounts.example.com",
+            "https://accounts.example.com"
+        ));
+        assert!(!pattern_matches(
+            "https://accounts.example.com",
+            "https://accounts.example.com/login"
+        ));
+    }
+
+    #[test]
+    fn ignores_opaque_patterns() {
+        assert!(!pattern_matches("mailto:*", "mailto:someone@example.com"));
+        assert!(!pattern_matches("*", "https://example.com/"));
+    }
+
+    #[test]
+    fn most_specific_handler_wins() {
+        let launchers = vec![
+            launcher("Site1234", &["https://example.com/*"]),
+            launcher("Docs1234", &["https://example.com/docs/*"]),
+            launcher("Mail1234", &["https://mail.example.com/*"]),
+        ];
+
+        let handler =
+            |url| best_handler(launchers.clone(), url).map(|launcher| launcher.browser.app_id.id);
+
+        assert_eq!(
+            handler("https://example.com/docs/a").as_deref(),
+            Some("Docs1234")
+        );
+        assert_eq!(
+            handler("https://example.com/blog").as_deref(),
+            Some("Site1234")
+        );
+        assert_eq!(
+            handler("https://mail.example.com/").as_deref(),
+            Some("Mail1234")
+        );
+        assert_eq!(handler("https://example.com.evil.net/docs/a"), None);
+    }
+}
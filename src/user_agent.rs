@@ -0,0 +1,37 @@
+//! User agents the web apps identify with.
+
+/// Placeholder replaced with the Chrome version of the bundled CEF.
+pub const CHROME_VERSION: &str = "{chrome}";
+
+/// Chrome major version assumed where CEF isn't loaded, like when fetching site details.
+pub const FALLBACK_CHROME_MAJOR: i32 = 145;
+
+pub const DESKTOP: &str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/{chrome} Safari/537.36";
+pub const MOBILE: &str = "Mozilla/5.0 (Linux; Android 10; K) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/{chrome} Mobile Safari/537.36";
+pub const SAFARI_IPHONE: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 18_5 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/18.5 Mobile/15E148 Safari/604.1";
+
+/// Presets offered by the editor.
+pub const PRESETS: [(&str, &str); 5] = [
+    ("Chrome (Linux)", DESKTOP),
+    ("Chrome (Android)", MOBILE),
+    (
+        "Firefox (Linux)",
+        "Mozilla/5.0 (X11; Linux x86_64; rv:140.0) Gecko/20100101 Firefox/140.0",
+    ),
+    (
+        "Safari (macOS)",
+        "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/18.5 Safari/605.1.15",
+    ),
+    ("Safari (iPhone)", SAFARI_IPHONE),
+];
+
+/// Fills in the Chrome version. Like Chrome's own reduced user agent, only the
+/// major version is revealed.
+pub fn expand(user_agent: &str, chrome_major: i32) -> String {
+    user_agent.replace(CHROME_VERSION, &format!("{}.0.0.0", chrome_major))
+}
+
+/// User agent for requests made outside of CEF.
+pub fn fallback() -> String {
+    expand(DESKTOP, FALLBACK_CHROME_MAJOR)
+}
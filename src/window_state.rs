@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use std::{fs::create_dir_all, path::PathBuf};
+
+use crate::APP_ID;
+
+/// Geometry of a web app's window when it was closed the last time.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WindowState {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub maximized: bool,
+    pub fullscreen: bool,
+}
+
+pub fn window_state_path(app_id: &str) -> Option<PathBuf> {
+    let xdg_data = dirs::data_dir()?;
+
+    Some(
+        xdg_data
+            .join(APP_ID)
+            .join("state")
+            .join(format!("{}.ron", app_id)),
+    )
+}
+
+impl WindowState {
+    pub fn load(app_id: &str) -> Option<Self> {
+        let content = std::fs::read_to_string(window_state_path(app_id)?).ok()?;
+
+        ron::from_str(&content).ok()
+    }
+
+    pub fn save(&self, app_id: &str) -> anyhow::Result<()> {
+        let Some(path) = window_state_path(app_id) else {
+            anyhow::bail!("cannot locate window state directory");
+        };
+
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+
+        std::fs::write(path, ron::ser::to_string(self)?)?;
+
+        Ok(())
+    }
+
+    pub fn delete(app_id: &str) {
+        if let Some(path) = window_state_path(app_id) {
+            if path.exists() {
+                if let Err(e) = std::fs::remove_file(path) {
+                    eprintln!("Failed to delete window state: {}", e);
+                }
+            }
+        }
+    }
+}